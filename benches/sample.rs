@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use keyframe_animate::prelude::*;
+
+/// Curve of `count` keyframes, one every 10ms.
+fn curve(count: usize) -> KeyframeVariableCurve<Transform> {
+    KeyframeVariableCurve {
+        keyframe_timestamps: (0..count).map(|i| i as f32 * 0.01).collect(),
+        keyframes: Keyframe::translation((0..count).map(|i| Vec3::X * i as f32).collect()),
+        channels: TransformChannels::TRANSLATION.into(),
+        ..Default::default()
+    }
+}
+
+/// Play the whole curve forward at 60 frames per second, searching the
+/// keyframes every frame or starting from the previous segment.
+fn playback(c: &mut Criterion) {
+    let mut group = c.benchmark_group("playback");
+    let target = Transform::default();
+    for count in [100, 1_000, 10_000] {
+        let curve = curve(count);
+        let frames = (count as f32 * 0.01 * 60.0) as usize;
+        group.bench_with_input(BenchmarkId::new("search", count), &curve, |b, curve| {
+            b.iter(|| {
+                for frame in 0..frames {
+                    black_box(curve.sample(frame as f32 / 60.0, &target));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("cursor", count), &curve, |b, curve| {
+            b.iter(|| {
+                let mut segment = 0;
+                for frame in 0..frames {
+                    black_box(curve.sample_from(frame as f32 / 60.0, &target, &mut segment));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, playback);
+criterion_main!(benches);
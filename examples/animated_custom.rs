@@ -1,26 +1,33 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::Uuid};
 use keyframe_animate::prelude::*;
 
-#[derive(Component, Default)]
+#[derive(Clone, Component, Default)]
 struct Custom(f32);
 
 impl Lerp<Custom> for Custom {
-    fn lerp(&self, other: &Self, scalar: f32, _: &Custom, _: &Option<Vec<String>>) -> Self {
+    type Channels = ();
+
+    fn lerp(&self, other: &Self, scalar: f32, _: &Custom, _: &()) -> Self {
         Custom(interpolation::lerp(&self.0, &other.0, &scalar))
     }
 }
 
+impl KeyframeClipUuid for Custom {
+    const CLIP_UUID: Uuid = Uuid::from_u128(0x5e1b7a94_0c3f_4d2e_b8a6_27f9d1c4e083);
+}
+
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugin(KeyframeAnimationPlugin)
+        .add_asset::<KeyframeAnimationClip<Custom>>()
         .add_startup_system(spawn)
         .add_system(keyframe_animation_player::<Custom>)
         .add_system(check_value)
         .run();
 }
 
-fn spawn(mut commands: Commands) {
+fn spawn(mut commands: Commands, mut animations: ResMut<Assets<KeyframeAnimationClip<Custom>>>) {
     // The animation API uses the `Name` component to target entities
     let custom = Name::new("custom");
 
@@ -40,12 +47,12 @@ fn spawn(mut commands: Commands) {
                 Keyframe(Custom(2.0)),
                 Keyframe(Custom(3.0)),
             ],
-            options: None,
+            ..Default::default()
         },
     );
 
     // Create the animation player, and set it to repeat
-    let mut player = KeyframeAnimationPlayer::new(animation);
+    let mut player = KeyframeAnimationPlayer::new(animations.add(animation));
     player.repeat();
 
     commands
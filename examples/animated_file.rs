@@ -0,0 +1,47 @@
+use bevy::{asset::AssetServerSettings, prelude::*};
+use keyframe_animate::prelude::*;
+
+fn main() {
+    App::new()
+        // Watch the clip file, edit it while the example runs to see the
+        // coin animation change
+        .insert_resource(AssetServerSettings {
+            watch_for_changes: true,
+            ..default()
+        })
+        .add_plugins(DefaultPlugins)
+        .add_plugin(KeyframeAnimationPlugin)
+        .add_startup_system(spawn)
+        .run();
+}
+
+fn spawn(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut textures: ResMut<Assets<TextureAtlas>>,
+) {
+    // Don't forget the camera ;-)
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+
+    // The clip is authored in `assets/coin.atlas.kanim.ron`, the `atlas`
+    // extension tells that it animates a `TextureAtlasSprite`
+    let mut player = KeyframeAnimationPlayer::<TextureAtlasSprite>::new(
+        asset_server.load("coin.atlas.kanim.ron"),
+    );
+    player.repeat();
+
+    commands
+        // Spawn a bevy sprite-sheet
+        .spawn_bundle(SpriteSheetBundle {
+            texture_atlas: textures.add(TextureAtlas::from_grid(
+                asset_server.load("coin.png"),
+                Vec2::new(16.0, 16.0),
+                5,
+                1,
+            )),
+            transform: Transform::from_scale(Vec3::splat(10.0)),
+            ..Default::default()
+        })
+        // The animation targets the entity named `coin` in the clip
+        .insert_bundle((Name::new("coin"), player));
+}
@@ -11,7 +11,11 @@ fn main() {
         .run();
 }
 
-fn spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn spawn(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut animations: ResMut<Assets<KeyframeAnimationClip<Handle<Image>>>>,
+) {
     // Don't forget the camera ;-)
     commands.spawn_bundle(OrthographicCameraBundle::new_2d());
 
@@ -44,12 +48,12 @@ fn spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
                 asset_server.load("APimg[8].png"),
                 asset_server.load("APimg[9].png"),
             ]),
-            options: None,
+            ..Default::default()
         },
     );
 
     // Create the animation player, and set it to repeat
-    let mut player = KeyframeAnimationPlayer::new(animation);
+    let mut player = KeyframeAnimationPlayer::new(animations.add(animation));
     player.repeat();
 
     commands
@@ -15,6 +15,7 @@ fn spawn(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut textures: ResMut<Assets<TextureAtlas>>,
+    mut animations: ResMut<Assets<KeyframeAnimationClip<TextureAtlasSprite>>>,
 ) {
     // Don't forget the camera ;-)
     commands.spawn_bundle(OrthographicCameraBundle::new_2d());
@@ -34,26 +35,33 @@ fn spawn(
         KeyframeVariableCurve {
             keyframe_timestamps: vec![0.0, duration, duration * 2., 3. * duration, 4. * duration],
             keyframes: Keyframe::index(vec![0, 1, 2, 3, 4]),
-            options: Some(vec!["index".to_string()]),
+            channels: TextureAtlasSpriteChannels::INDEX,
+            ..Default::default()
         },
     );
 
-    // Create the animation player, and set it to repeat
-    let mut player = KeyframeAnimationPlayer::new(animation);
-    player.repeat();
+    // All the coins share the same animation clip
+    let animation = animations.add(animation);
+    let texture_atlas = textures.add(TextureAtlas::from_grid(
+        asset_server.load("coin.png"),
+        Vec2::new(16.0, 16.0),
+        5,
+        1,
+    ));
+    for i in -2..=2 {
+        // Create the animation player, and set it to repeat
+        let mut player = KeyframeAnimationPlayer::new(animation.clone());
+        player.repeat();
 
-    commands
-        // Spawn a bevy sprite-sheet
-        .spawn_bundle(SpriteSheetBundle {
-            texture_atlas: textures.add(TextureAtlas::from_grid(
-                asset_server.load("coin.png"),
-                Vec2::new(16.0, 16.0),
-                5,
-                1,
-            )),
-            transform: Transform::from_scale(Vec3::splat(10.0)),
-            ..Default::default()
-        })
-        // Add the Name component, and the animation player
-        .insert_bundle((coin, player));
+        commands
+            // Spawn a bevy sprite-sheet
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: texture_atlas.clone(),
+                transform: Transform::from_xyz(i as f32 * 200.0, 0.0, 0.0)
+                    .with_scale(Vec3::splat(10.0)),
+                ..Default::default()
+            })
+            // Add the Name component, and the animation player
+            .insert_bundle((coin.clone(), player));
+    }
 }
@@ -22,6 +22,7 @@ fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut animations: ResMut<Assets<KeyframeAnimationClip<Transform>>>,
 ) {
     // Camera
     commands.spawn_bundle(PerspectiveCameraBundle {
@@ -36,23 +37,27 @@ fn setup(
 
     // Creating the animation
     let mut animation = KeyframeAnimationClip::default();
-    // A curve can modify a single part of a transform, here the translation
+    // A curve can modify a single part of a transform, here the translation.
+    // A closed Catmull-Rom spline turns the four corners into a smooth loop
     animation.add_curve_to_path(
         KeyframeEntityPath {
             parts: vec![planet.clone()],
         },
         KeyframeVariableCurve {
-            options: Some(vec!["translation".to_string()]),
-            keyframe_timestamps: vec![0.0, 1.0, 2.0, 3.0, 4.0],
-            keyframes: Keyframe::translation(vec![
-                Vec3::new(1.0, 0.0, 1.0),
-                Vec3::new(-1.0, 0.0, 1.0),
-                Vec3::new(-1.0, 0.0, -1.0),
-                Vec3::new(1.0, 0.0, -1.0),
-                // in case seamless looping is wanted, the last keyframe should
-                // be the same as the first one
-                Vec3::new(1.0, 0.0, 1.0),
-            ]),
+            channels: TransformChannels::TRANSLATION.into(),
+            ..KeyframeVariableCurve::catmull_rom(
+                vec![0.0, 1.0, 2.0, 3.0, 4.0],
+                Keyframe::translation(vec![
+                    Vec3::new(1.0, 0.0, 1.0),
+                    Vec3::new(-1.0, 0.0, 1.0),
+                    Vec3::new(-1.0, 0.0, -1.0),
+                    Vec3::new(1.0, 0.0, -1.0),
+                    // in case seamless looping is wanted, the last keyframe should
+                    // be the same as the first one
+                    Vec3::new(1.0, 0.0, 1.0),
+                ]),
+                true,
+            )
         },
     );
     // Or it can modify the rotation of the transform.
@@ -63,7 +68,7 @@ fn setup(
             parts: vec![planet.clone(), orbit_controller.clone()],
         },
         KeyframeVariableCurve {
-            options: Some(vec!["rotation".to_string()]),
+            channels: TransformChannels::ROTATION.into(),
             keyframe_timestamps: vec![0.0, 1.0, 2.0, 3.0, 4.0],
             keyframes: Keyframe::rotation(vec![
                 Quat::from_axis_angle(Vec3::Y, 0.0),
@@ -72,6 +77,7 @@ fn setup(
                 Quat::from_axis_angle(Vec3::Y, 3.0 * FRAC_PI_2),
                 Quat::from_axis_angle(Vec3::Y, 0.0),
             ]),
+            ..Default::default()
         },
     );
     // If a curve in an animation is shorter than the other, it will not repeat
@@ -82,7 +88,7 @@ fn setup(
             parts: vec![planet.clone(), orbit_controller.clone(), satellite.clone()],
         },
         KeyframeVariableCurve {
-            options: Some(vec!["scale".to_string()]),
+            channels: TransformChannels::SCALE.into(),
             keyframe_timestamps: vec![0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0],
             keyframes: Keyframe::scale(vec![
                 Vec3::splat(0.8),
@@ -95,6 +101,9 @@ fn setup(
                 Vec3::splat(1.2),
                 Vec3::splat(0.8),
             ]),
+            // Each segment can be eased instead of linearly interpolated
+            ease: Ease::SineInOut,
+            ..Default::default()
         },
     );
     // There can be more than one curve targeting the same entity path
@@ -103,7 +112,7 @@ fn setup(
             parts: vec![planet.clone(), orbit_controller.clone(), satellite.clone()],
         },
         KeyframeVariableCurve {
-            options: Some(vec!["rotation".to_string()]),
+            channels: TransformChannels::ROTATION.into(),
             keyframe_timestamps: vec![0.0, 1.0, 2.0, 3.0, 4.0],
             keyframes: Keyframe::rotation(vec![
                 Quat::from_axis_angle(Vec3::Y, 0.0),
@@ -112,11 +121,12 @@ fn setup(
                 Quat::from_axis_angle(Vec3::Y, 3.0 * FRAC_PI_2),
                 Quat::from_axis_angle(Vec3::Y, 0.0),
             ]),
+            ..Default::default()
         },
     );
 
     // Create the animation player, and set it to repeat
-    let mut player = KeyframeAnimationPlayer::new(animation);
+    let mut player = KeyframeAnimationPlayer::new(animations.add(animation));
     player.repeat();
 
     // Create the scene that will be animated
@@ -0,0 +1,561 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    asset::Asset,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    lerp::Lerp,
+    repeat::{clip_elapsed, RepeatMode},
+    target::{HierarchyChanges, KeyframeTargetCache},
+    Keyframe, KeyframeAnimationClip,
+};
+
+/// Node of a blend tree, blending its children by the value of parameters of
+/// the [`KeyframeBlendTreePlayer`]. A missing parameter is `0.0`.
+pub enum KeyframeBlendTree<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    /// Leaf of the tree.
+    Clip(Handle<KeyframeAnimationClip<T>>),
+    /// Children at thresholds of a parameter, e.g. walk, run and sprint at
+    /// increasing speeds. The two children around the value of the parameter
+    /// are blended linearly.
+    Linear {
+        parameter: String,
+        children: Vec<(f32, KeyframeBlendTree<T>)>,
+    },
+    /// Children at directions of two parameters, e.g. strafing clips around
+    /// a velocity, with at most one child at the center for idling. The two
+    /// children around the direction of the parameters are blended, along
+    /// with the center child below their magnitude.
+    SimpleDirectional {
+        parameters: (String, String),
+        children: Vec<(Vec2, KeyframeBlendTree<T>)>,
+    },
+    /// Children at arbitrary positions of two parameters, e.g. several clips
+    /// in the same direction at different speeds. Each child is weighted by
+    /// its proximity to the parameters relative to the other children.
+    Freeform {
+        parameters: (String, String),
+        children: Vec<(Vec2, KeyframeBlendTree<T>)>,
+    },
+}
+
+impl<T> KeyframeBlendTree<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    /// Clips of the leaves of the tree, with their weight multiplied by
+    /// `weight`.
+    fn leaves<'a>(
+        &'a self,
+        parameters: &HashMap<String, f32>,
+        weight: f32,
+        leaves: &mut Vec<(&'a Handle<KeyframeAnimationClip<T>>, f32)>,
+    ) {
+        let parameter = |name: &String| parameters.get(name).copied().unwrap_or(0.0);
+        let (weights, children): (Vec<_>, Vec<_>) = match self {
+            KeyframeBlendTree::Clip(handle) => {
+                leaves.push((handle, weight));
+                return;
+            }
+            KeyframeBlendTree::Linear {
+                parameter: name,
+                children,
+            } => {
+                let thresholds: Vec<_> = children.iter().map(|(threshold, _)| *threshold).collect();
+                (
+                    linear_weights(parameter(name), &thresholds),
+                    children.iter().map(|(_, child)| child).collect(),
+                )
+            }
+            KeyframeBlendTree::SimpleDirectional {
+                parameters: (x, y),
+                children,
+            } => {
+                let positions: Vec<_> = children.iter().map(|(position, _)| *position).collect();
+                (
+                    simple_directional_weights(Vec2::new(parameter(x), parameter(y)), &positions),
+                    children.iter().map(|(_, child)| child).collect(),
+                )
+            }
+            KeyframeBlendTree::Freeform {
+                parameters: (x, y),
+                children,
+            } => {
+                let positions: Vec<_> = children.iter().map(|(position, _)| *position).collect();
+                (
+                    freeform_weights(Vec2::new(parameter(x), parameter(y)), &positions),
+                    children.iter().map(|(_, child)| child).collect(),
+                )
+            }
+        };
+        for (child_weight, child) in weights.into_iter().zip(children) {
+            if child_weight > 0.0 {
+                child.leaves(parameters, weight * child_weight, leaves);
+            }
+        }
+    }
+}
+
+/// Weights of children at `thresholds` for the value `point`, clamped to the
+/// first and last thresholds.
+fn linear_weights(point: f32, thresholds: &[f32]) -> Vec<f32> {
+    let mut weights = vec![0.0; thresholds.len()];
+    let mut order: Vec<_> = (0..thresholds.len()).collect();
+    order.sort_by(|a, b| thresholds[*a].total_cmp(&thresholds[*b]));
+    let (first, last) = match (order.first(), order.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return weights,
+    };
+    if point <= thresholds[first] {
+        weights[first] = 1.0;
+    } else if point >= thresholds[last] {
+        weights[last] = 1.0;
+    } else {
+        for pair in order.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if thresholds[from] <= point && point < thresholds[to] {
+                let scalar = (point - thresholds[from]) / (thresholds[to] - thresholds[from]);
+                weights[from] = 1.0 - scalar;
+                weights[to] = scalar;
+                break;
+            }
+        }
+    }
+    weights
+}
+
+/// Weights of children at directions `positions` for the direction `point`.
+fn simple_directional_weights(point: Vec2, positions: &[Vec2]) -> Vec<f32> {
+    let mut weights = vec![0.0; positions.len()];
+    if positions.is_empty() {
+        return weights;
+    }
+    let center = positions
+        .iter()
+        .position(|position| position.length_squared() < f32::EPSILON);
+    if point.length_squared() < f32::EPSILON {
+        match center {
+            Some(center) => weights[center] = 1.0,
+            None => weights.fill(1.0 / positions.len() as f32),
+        }
+        return weights;
+    }
+    // Children right before and right after the point, counterclockwise
+    let angle =
+        |position: Vec2| (position.y.atan2(position.x) - point.y.atan2(point.x)).rem_euclid(TAU);
+    let around = |after: bool| {
+        (0..positions.len())
+            .filter(|index| Some(*index) != center)
+            .min_by(|a, b| {
+                let (a, b) = (angle(positions[*a]), angle(positions[*b]));
+                let (a, b) = if after { (a, b) } else { (TAU - a, TAU - b) };
+                a.total_cmp(&b)
+            })
+    };
+    let (before, after) = match (around(false), around(true)) {
+        (Some(before), Some(after)) => (before, after),
+        // Only the center child
+        _ => {
+            weights[center.unwrap()] = 1.0;
+            return weights;
+        }
+    };
+    // Write the point as a combination of the two children
+    let (a, b) = (positions[before], positions[after]);
+    let determinant = a.perp_dot(b);
+    if before == after || determinant.abs() < f32::EPSILON {
+        let closest = if angle(a).min(TAU - angle(a)) <= angle(b).min(TAU - angle(b)) {
+            before
+        } else {
+            after
+        };
+        let position = positions[closest];
+        weights[closest] = (point.dot(position) / position.length_squared()).clamp(0.0, 1.0);
+    } else {
+        weights[before] = point.perp_dot(b) / determinant;
+        weights[after] = a.perp_dot(point) / determinant;
+        weights[before] = weights[before].max(0.0);
+        weights[after] = weights[after].max(0.0);
+    }
+    let total: f32 = weights.iter().sum();
+    match center {
+        // The center child fills in below the magnitude of the children
+        Some(center) if total < 1.0 => weights[center] = 1.0 - total,
+        _ if total > 0.0 => weights.iter_mut().for_each(|weight| *weight /= total),
+        _ => weights[before] = 1.0,
+    }
+    weights
+}
+
+/// Weights of children at `positions` for the point `point`, by gradient band
+/// interpolation.
+fn freeform_weights(point: Vec2, positions: &[Vec2]) -> Vec<f32> {
+    let mut weights: Vec<_> = positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| {
+            positions
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other)| {
+                    let edge = *other - *position;
+                    if edge.length_squared() < f32::EPSILON {
+                        return 1.0;
+                    }
+                    1.0 - (point - *position).dot(edge) / edge.length_squared()
+                })
+                .fold(1.0f32, f32::min)
+                .max(0.0)
+        })
+        .collect();
+    let total: f32 = weights.iter().sum();
+    if total > 0.0 {
+        weights.iter_mut().for_each(|weight| *weight /= total);
+    }
+    weights
+}
+
+/// Plays a [`KeyframeBlendTree`] from the entity as an animation root. The
+/// clips of the tree are played in sync: they share the same normalized
+/// time, advanced by the average duration of the clips weighted by their
+/// blend weight, so that clips of different durations line up.
+#[derive(Component)]
+pub struct KeyframeBlendTreePlayer<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    tree: KeyframeBlendTree<T>,
+    parameters: HashMap<String, f32>,
+    paused: bool,
+    repeat_mode: RepeatMode,
+    speed: f32,
+    normalized_time: f32,
+    target_cache: KeyframeTargetCache,
+    /// Values of the targets when the player started, for relative keyframes
+    bases: HashMap<Entity, T>,
+}
+
+impl<T> KeyframeBlendTreePlayer<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    /// Create a player repeating the tree
+    pub fn new(tree: KeyframeBlendTree<T>) -> Self {
+        Self {
+            tree,
+            parameters: HashMap::default(),
+            paused: false,
+            repeat_mode: RepeatMode::Loop,
+            speed: 1.0,
+            normalized_time: 0.0,
+            target_cache: KeyframeTargetCache::default(),
+            bases: HashMap::default(),
+        }
+    }
+
+    /// Blend tree played
+    pub fn tree(&self) -> &KeyframeBlendTree<T> {
+        &self.tree
+    }
+
+    /// Mutable access to the blend tree played
+    pub fn tree_mut(&mut self) -> &mut KeyframeBlendTree<T> {
+        &mut self.tree
+    }
+
+    /// Value of a parameter of the tree
+    pub fn parameter(&self, name: &str) -> f32 {
+        self.parameters.get(name).copied().unwrap_or(0.0)
+    }
+
+    /// Set the value of a parameter of the tree
+    pub fn set_parameter(&mut self, name: impl Into<String>, value: f32) -> &mut Self {
+        self.parameters.insert(name.into(), value);
+        self
+    }
+
+    /// How the clips repeat
+    pub fn repeat_mode(&self) -> RepeatMode {
+        self.repeat_mode
+    }
+
+    /// Set how the clips repeat
+    pub fn set_repeat_mode(&mut self, repeat_mode: RepeatMode) -> &mut Self {
+        self.repeat_mode = repeat_mode;
+        self
+    }
+
+    /// Pause the animation
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Unpause the animation
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Is the animation paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Speed of the animation playback
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Set the speed of the animation playback
+    pub fn set_speed(&mut self, speed: f32) -> &mut Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Time elapsed playing the clips, as a fraction of their duration. It
+    /// counts the loops, e.g. `1.5` is halfway through the second loop.
+    pub fn normalized_time(&self) -> f32 {
+        self.normalized_time
+    }
+
+    /// Seek to a specific fraction of the clips
+    pub fn set_normalized_time(&mut self, normalized_time: f32) -> &mut Self {
+        self.normalized_time = normalized_time;
+        self
+    }
+}
+
+/// System that plays the [`KeyframeBlendTreePlayer`]s, blending the curves of
+/// the clips of their tree by their weight.
+pub fn keyframe_blend_tree_player<T>(
+    time: Res<Time>,
+    animations: Res<Assets<KeyframeAnimationClip<T>>>,
+    mut players: Query<(Entity, &mut KeyframeBlendTreePlayer<T>)>,
+    mut targets: Query<&mut T>,
+    names: Query<&Name>,
+    children: Query<&Children>,
+    hierarchy_changes: HierarchyChanges,
+) where
+    T: Component + Clone,
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    let hierarchy_changed = !hierarchy_changes.is_empty();
+    for (entity, mut player) in players.iter_mut() {
+        if hierarchy_changed {
+            player.target_cache.clear();
+        }
+        if player.paused && !player.is_changed() {
+            continue;
+        }
+        let mut leaves = Vec::new();
+        player.tree.leaves(&player.parameters, 1.0, &mut leaves);
+        // Skip the clips that aren't loaded yet
+        let leaves: Vec<_> = leaves
+            .into_iter()
+            .filter_map(|(handle, weight)| Some((animations.get(handle)?, weight)))
+            .collect();
+        let total: f32 = leaves.iter().map(|(_, weight)| weight).sum();
+        if total <= 0.0 {
+            continue;
+        }
+        let duration = leaves
+            .iter()
+            .map(|(clip, weight)| clip.duration() * weight)
+            .sum::<f32>()
+            / total;
+        if !player.paused && duration > 0.0 {
+            player.normalized_time += time.delta_seconds() * player.speed / duration;
+        }
+        let normalized_time = clip_elapsed(player.normalized_time, 1.0, player.repeat_mode);
+
+        // Paths animated by any of the clips
+        let mut paths = Vec::new();
+        let mut seen = HashSet::default();
+        for path in leaves.iter().flat_map(|(clip, _)| clip.curves().keys()) {
+            if seen.insert(path) {
+                paths.push(path);
+            }
+        }
+        let mut target_cache = std::mem::take(&mut player.target_cache);
+        let mut bases = std::mem::take(&mut player.bases);
+        for path in paths {
+            let target = match target_cache.get(entity, path, &children, &names) {
+                Some(target) => target,
+                None => continue,
+            };
+            let mut object = match targets.get_mut(target) {
+                Ok(object) => object,
+                // The entity exists but doesn't have the animated component
+                Err(_) => continue,
+            };
+            // Running weighted average of the clips animating the path
+            let mut keyframe: Option<Keyframe<T>> = None;
+            let mut path_total = 0.0;
+            for (clip, weight) in &leaves {
+                let base = clip.capture_base(path, &mut bases, target, &object);
+                let elapsed = normalized_time * clip.duration();
+                let clip_target = clip.sampling_target(base, &object);
+                let sampled = match clip.sample(path, elapsed, clip_target, base, None) {
+                    Some(sampled) => sampled,
+                    None => continue,
+                };
+                path_total += weight;
+                keyframe = Some(match keyframe {
+                    Some(keyframe) => {
+                        keyframe.lerp(&sampled, weight / path_total, &*object, &Default::default())
+                    }
+                    None => sampled,
+                });
+            }
+            if let Some(keyframe) = keyframe {
+                *object = keyframe.0;
+            }
+        }
+        player.target_cache = target_cache;
+        player.bases = bases;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::AssetPlugin, core::CorePlugin};
+
+    use super::*;
+
+    use crate::{channels::TransformChannels, KeyframeEntityPath, KeyframeVariableCurve};
+
+    fn assert_weights(weights: Vec<f32>, expected: &[f32]) {
+        assert_eq!(weights.len(), expected.len());
+        for (weight, expected) in weights.iter().zip(expected) {
+            assert!(
+                (weight - expected).abs() < 1e-4,
+                "{:?} != {:?}",
+                weights,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn linear_weights_blend_around_thresholds() {
+        let thresholds = [0.0, 2.0, 6.0];
+        assert_weights(linear_weights(-1.0, &thresholds), &[1.0, 0.0, 0.0]);
+        assert_weights(linear_weights(1.5, &thresholds), &[0.25, 0.75, 0.0]);
+        assert_weights(linear_weights(3.0, &thresholds), &[0.0, 0.75, 0.25]);
+        assert_weights(linear_weights(10.0, &thresholds), &[0.0, 0.0, 1.0]);
+        // Unsorted thresholds
+        assert_weights(linear_weights(4.0, &[6.0, 2.0]), &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn directional_weights_blend_neighbours_and_center() {
+        let positions = [Vec2::ZERO, Vec2::X, Vec2::Y, -Vec2::X, -Vec2::Y];
+        assert_weights(
+            simple_directional_weights(Vec2::ZERO, &positions),
+            &[1.0, 0.0, 0.0, 0.0, 0.0],
+        );
+        assert_weights(
+            simple_directional_weights(Vec2::new(0.5, 0.5), &positions),
+            &[0.0, 0.5, 0.5, 0.0, 0.0],
+        );
+        assert_weights(
+            simple_directional_weights(Vec2::new(0.0, -0.25), &positions),
+            &[0.75, 0.0, 0.0, 0.0, 0.25],
+        );
+        // Outside of the children, normalized
+        assert_weights(
+            simple_directional_weights(Vec2::new(-2.0, 2.0), &positions),
+            &[0.0, 0.0, 0.5, 0.5, 0.0],
+        );
+    }
+
+    #[test]
+    fn freeform_weights_match_children() {
+        let positions = [Vec2::ZERO, Vec2::Y, Vec2::new(0.0, 3.0), Vec2::X];
+        assert_weights(freeform_weights(Vec2::Y, &positions), &[0.0, 1.0, 0.0, 0.0]);
+        assert_weights(
+            freeform_weights(Vec2::new(0.0, 2.0), &positions),
+            &[0.0, 0.5, 0.5, 0.0],
+        );
+        let weights = freeform_weights(Vec2::new(0.3, 0.4), &positions);
+        assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn clips_of_different_durations_line_up() {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<KeyframeAnimationClip<Transform>>();
+        let mut world = std::mem::take(&mut app.world);
+
+        // Clips of 1s and 2s moving from the origin to `to`
+        let mut clip = |duration: f32, to: Vec3| {
+            let mut clip = KeyframeAnimationClip::default();
+            clip.add_curve_to_path(
+                KeyframeEntityPath {
+                    parts: vec![Name::new("hero")],
+                },
+                KeyframeVariableCurve {
+                    keyframe_timestamps: vec![0.0, duration],
+                    keyframes: Keyframe::translation(vec![Vec3::ZERO, to]),
+                    channels: TransformChannels::TRANSLATION.into(),
+                    ..Default::default()
+                },
+            );
+            world
+                .resource_mut::<Assets<KeyframeAnimationClip<Transform>>>()
+                .add(clip)
+        };
+        let walk = clip(1.0, Vec3::X);
+        let run = clip(2.0, Vec3::Y);
+
+        let mut player = KeyframeBlendTreePlayer::new(KeyframeBlendTree::Linear {
+            parameter: "speed".to_string(),
+            children: vec![
+                (0.0, KeyframeBlendTree::Clip(walk)),
+                (1.0, KeyframeBlendTree::Clip(run)),
+            ],
+        });
+        player.set_parameter("speed", 0.5).set_normalized_time(0.5);
+        let hero = world
+            .spawn()
+            .insert_bundle((Name::new("hero"), Transform::default(), player))
+            .id();
+
+        let mut time = world.resource_mut::<Time>();
+        time.update();
+        let delta = time.delta_seconds();
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(keyframe_blend_tree_player::<Transform>);
+        stage.run(&mut world);
+
+        // Advanced by the weighted average of the durations
+        let player = world
+            .get::<KeyframeBlendTreePlayer<Transform>>(hero)
+            .unwrap();
+        let normalized_time = 0.5 + delta / 1.5;
+        assert!((player.normalized_time() - normalized_time).abs() < 1e-4);
+        // Both clips are at the same fraction of their duration, blended half
+        // and half
+        let translation = world.get::<Transform>(hero).unwrap().translation;
+        let expected = Vec3::new(normalized_time, normalized_time, 0.0) * 0.5;
+        assert!(
+            translation.abs_diff_eq(expected, 1e-4),
+            "{:?} != {:?}",
+            translation,
+            expected
+        );
+    }
+}
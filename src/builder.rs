@@ -0,0 +1,334 @@
+use crate::{
+    ease::Ease, error::KeyframeError, lerp::Lerp, marker::KeyframeMarker,
+    spline::KeyframeInterpolation, Keyframe, KeyframeAnimationClip, KeyframeBlendMode,
+    KeyframeChannels, KeyframeEntityPath, KeyframeMode, KeyframeVariableCurve,
+};
+
+/// Builder of a [`KeyframeVariableCurve`], adding its keyframes one by one.
+pub struct KeyframeCurveBuilder<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    curve: KeyframeVariableCurve<T>,
+    /// Mode of each keyframe, set on the curve if any isn't absolute
+    keyframe_modes: Vec<KeyframeMode>,
+}
+
+impl<T> Default for KeyframeCurveBuilder<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    fn default() -> Self {
+        Self {
+            curve: KeyframeVariableCurve::default(),
+            keyframe_modes: Vec::new(),
+        }
+    }
+}
+
+impl<T> KeyframeCurveBuilder<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    /// Set the attributes of `T` animated by the curve
+    pub fn channel(mut self, channels: impl Into<KeyframeChannels<T>>) -> Self {
+        self.curve.channels = channels.into();
+        self
+    }
+
+    /// Add a keyframe at `timestamp`. The keyframes don't need to be added in
+    /// order.
+    pub fn key(self, timestamp: f32, keyframe: impl Into<Keyframe<T>>) -> Self {
+        self.key_with_mode(timestamp, keyframe, KeyframeMode::Absolute)
+    }
+
+    /// Add a keyframe at `timestamp`, applied as an offset from the value of
+    /// the target when the clip starts
+    pub fn relative_key(self, timestamp: f32, keyframe: impl Into<Keyframe<T>>) -> Self {
+        self.key_with_mode(timestamp, keyframe, KeyframeMode::Relative)
+    }
+
+    /// Add a keyframe at `timestamp` holding the value of the target when
+    /// the clip starts
+    pub fn current_key(self, timestamp: f32) -> Self
+    where
+        T: Default,
+    {
+        self.key_with_mode(timestamp, T::default(), KeyframeMode::Current)
+    }
+
+    /// Add a keyframe at `timestamp` applied with `mode`
+    pub fn key_with_mode(
+        mut self,
+        timestamp: f32,
+        keyframe: impl Into<Keyframe<T>>,
+        mode: KeyframeMode,
+    ) -> Self {
+        self.curve.keyframe_timestamps.push(timestamp);
+        self.curve.keyframes.push(keyframe.into());
+        self.keyframe_modes.push(mode);
+        self
+    }
+
+    /// Set the easing of every segment of the curve
+    pub fn ease(mut self, ease: Ease) -> Self {
+        self.curve.ease = ease;
+        self
+    }
+
+    /// Set the easing of each segment of the curve, overriding the easing of
+    /// the whole curve
+    pub fn segment_eases(mut self, segment_eases: Vec<Ease>) -> Self {
+        self.curve.segment_eases = Some(segment_eases);
+        self
+    }
+
+    /// Set how the values are interpolated between two keyframes
+    pub fn interpolation(mut self, interpolation: KeyframeInterpolation<T>) -> Self {
+        self.curve.interpolation = interpolation;
+        self
+    }
+
+    /// Sort the keyframes by timestamp and validate the curve
+    pub fn build(mut self) -> Result<KeyframeVariableCurve<T>, KeyframeError> {
+        let curve = &mut self.curve;
+        if curve.keyframe_timestamps.len() == curve.keyframes.len() {
+            let mut keys: Vec<_> = std::mem::take(&mut curve.keyframe_timestamps)
+                .into_iter()
+                .zip(std::mem::take(&mut curve.keyframes))
+                .zip(self.keyframe_modes)
+                .collect();
+            // Stable, so that duplicates are reported in the order they were
+            // added
+            keys.sort_by(|((a, _), _), ((b, _), _)| a.total_cmp(b));
+            let (keys, keyframe_modes): (Vec<_>, Vec<_>) = keys.into_iter().unzip();
+            (curve.keyframe_timestamps, curve.keyframes) = keys.into_iter().unzip();
+            if keyframe_modes
+                .iter()
+                .any(|mode| *mode != KeyframeMode::Absolute)
+            {
+                curve.keyframe_modes = Some(keyframe_modes);
+            }
+        }
+        self.curve.validate()?;
+        Ok(self.curve)
+    }
+}
+
+/// Builder of a [`KeyframeAnimationClip`]. Each call to
+/// [`path`](Self::path) starts a new curve, built by the following calls:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use keyframe_animate::prelude::*;
+/// let clip = KeyframeAnimationClip::builder()
+///     .path("planet/orbit_controller")
+///     .channel(TransformChannels::TRANSLATION)
+///     .key(0.0, Transform::from_xyz(0.0, 0.0, 0.0))
+///     .key(1.0, Transform::from_xyz(1.0, 0.0, 0.0))
+///     .ease(Ease::CubicInOut)
+///     .build()
+///     .unwrap();
+/// assert_eq!(clip.duration(), 1.0);
+/// ```
+pub struct KeyframeClipBuilder<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    clip: KeyframeAnimationClip<T>,
+    /// Curve being built, with its path
+    curve: Option<(KeyframeEntityPath, KeyframeCurveBuilder<T>)>,
+    /// First invalid curve, reported when building the clip
+    error: Option<KeyframeError>,
+}
+
+impl<T> Default for KeyframeClipBuilder<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    fn default() -> Self {
+        Self {
+            clip: KeyframeAnimationClip::default(),
+            curve: None,
+            error: None,
+        }
+    }
+}
+
+impl<T> KeyframeClipBuilder<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    /// Start a curve animating the entity at `path`, e.g.
+    /// `"planet/orbit_controller"`
+    pub fn path(mut self, path: impl Into<KeyframeEntityPath>) -> Self {
+        self.finish_curve();
+        self.curve = Some((path.into(), KeyframeCurveBuilder::default()));
+        self
+    }
+
+    /// Set the attributes of `T` animated by the current curve
+    pub fn channel(self, channels: impl Into<KeyframeChannels<T>>) -> Self {
+        self.map_curve(|curve| curve.channel(channels))
+    }
+
+    /// Add a keyframe at `timestamp` to the current curve
+    pub fn key(self, timestamp: f32, keyframe: impl Into<Keyframe<T>>) -> Self {
+        self.map_curve(|curve| curve.key(timestamp, keyframe))
+    }
+
+    /// Add a relative keyframe at `timestamp` to the current curve
+    pub fn relative_key(self, timestamp: f32, keyframe: impl Into<Keyframe<T>>) -> Self {
+        self.map_curve(|curve| curve.relative_key(timestamp, keyframe))
+    }
+
+    /// Add a keyframe at `timestamp` holding the value of the target when
+    /// the clip starts to the current curve
+    pub fn current_key(self, timestamp: f32) -> Self
+    where
+        T: Default,
+    {
+        self.map_curve(|curve| curve.current_key(timestamp))
+    }
+
+    /// Add a keyframe at `timestamp` applied with `mode` to the current curve
+    pub fn key_with_mode(
+        self,
+        timestamp: f32,
+        keyframe: impl Into<Keyframe<T>>,
+        mode: KeyframeMode,
+    ) -> Self {
+        self.map_curve(|curve| curve.key_with_mode(timestamp, keyframe, mode))
+    }
+
+    /// Set the easing of every segment of the current curve
+    pub fn ease(self, ease: Ease) -> Self {
+        self.map_curve(|curve| curve.ease(ease))
+    }
+
+    /// Set the easing of each segment of the current curve
+    pub fn segment_eases(self, segment_eases: Vec<Ease>) -> Self {
+        self.map_curve(|curve| curve.segment_eases(segment_eases))
+    }
+
+    /// Set how the values of the current curve are interpolated
+    pub fn interpolation(self, interpolation: KeyframeInterpolation<T>) -> Self {
+        self.map_curve(|curve| curve.interpolation(interpolation))
+    }
+
+    /// Add a marker to the clip
+    pub fn marker(self, timestamp: f32, name: impl Into<String>) -> Self {
+        self.marker_with(timestamp, name, String::new())
+    }
+
+    /// Add a marker to the clip, sent with `payload`
+    pub fn marker_with(
+        mut self,
+        timestamp: f32,
+        name: impl Into<String>,
+        payload: impl Into<String>,
+    ) -> Self {
+        self.clip.add_marker(KeyframeMarker {
+            timestamp,
+            name: name.into(),
+            payload: payload.into(),
+        });
+        self
+    }
+
+    /// Set how the values of the clip are applied
+    pub fn blend_mode(mut self, blend_mode: KeyframeBlendMode) -> Self {
+        self.clip.set_blend_mode(blend_mode);
+        self
+    }
+
+    /// Build the clip, returning the error of the first invalid curve
+    pub fn build(mut self) -> Result<KeyframeAnimationClip<T>, KeyframeError> {
+        self.finish_curve();
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.clip),
+        }
+    }
+
+    /// Update the current curve, starting one on the root entity if no path
+    /// was given yet
+    fn map_curve(
+        mut self,
+        f: impl FnOnce(KeyframeCurveBuilder<T>) -> KeyframeCurveBuilder<T>,
+    ) -> Self {
+        let (path, curve) = self.curve.take().unwrap_or_else(|| {
+            (
+                KeyframeEntityPath::default(),
+                KeyframeCurveBuilder::default(),
+            )
+        });
+        self.curve = Some((path, f(curve)));
+        self
+    }
+
+    /// Add the current curve to the clip
+    fn finish_curve(&mut self) {
+        if let Some((path, curve)) = self.curve.take() {
+            match curve.build() {
+                Ok(curve) => self.clip.add_curve_to_path(path, curve),
+                Err(error) => {
+                    self.error.get_or_insert(error);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::channels::TransformChannels;
+
+    #[test]
+    fn builder_adds_sorted_curves_to_paths() {
+        let clip = KeyframeAnimationClip::builder()
+            .path("planet/orbit_controller")
+            .channel(TransformChannels::ROTATION)
+            .key(1.0, Transform::from_rotation(Quat::from_rotation_y(1.0)))
+            .key(0.0, Transform::identity())
+            .path("planet")
+            .key(2.0, Transform::from_xyz(1.0, 0.0, 0.0))
+            .marker(0.5, "halfway")
+            .marker_with(1.5, "land", "dust")
+            .build()
+            .unwrap();
+        assert_eq!(clip.duration(), 2.0);
+        assert_eq!(clip.markers()[0].name, "halfway");
+        assert_eq!(clip.markers()[0].payload, "");
+        assert_eq!(clip.markers()[1].name, "land");
+        assert_eq!(clip.markers()[1].payload, "dust");
+
+        let path = KeyframeEntityPath {
+            parts: vec![Name::new("planet"), Name::new("orbit_controller")],
+        };
+        let curve = &clip.curves()[&path][0];
+        assert_eq!(curve.channels, TransformChannels::ROTATION.into());
+        assert_eq!(curve.keyframe_timestamps, [0.0, 1.0]);
+        assert_eq!(curve.keyframes[0].0.rotation, Quat::IDENTITY);
+        assert_eq!(clip.curves()[&KeyframeEntityPath::from("planet")].len(), 1);
+    }
+
+    #[test]
+    fn builder_reports_invalid_curves() {
+        let error = KeyframeAnimationClip::builder()
+            .path("planet")
+            .key(0.0, Transform::identity())
+            .key(0.0, Transform::identity())
+            .path("moon")
+            .key(f32::NAN, Transform::identity())
+            .build()
+            .err();
+        assert!(matches!(
+            error,
+            Some(KeyframeError::DuplicateTimestamp { index: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use bitflags::bitflags;
+
+/// Error returned when parsing a channel name that the component doesn't
+/// have.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownChannel(pub String);
+
+impl fmt::Display for UnknownChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown animation channel `{}`", self.0)
+    }
+}
+
+impl Error for UnknownChannel {}
+
+/// Declare the channels of a component as bitflags, that can be parsed from
+/// their snake case names.
+macro_rules! channels {
+    (
+        $(#[$($meta:tt)*])*
+        pub struct $name:ident {
+            $(
+                $(#[$($flag_meta:tt)*])*
+                const $flag:ident = $value:expr, $flag_name:literal;
+            )+
+        }
+    ) => {
+        bitflags! {
+            $(#[$($meta)*])*
+            pub struct $name: u32 {
+                $(
+                    $(#[$($flag_meta)*])*
+                    const $flag = $value;
+                )+
+            }
+        }
+
+        impl $name {
+            /// Parse the channels from their names.
+            pub fn from_names<'a>(
+                names: impl IntoIterator<Item = &'a str>,
+            ) -> Result<Self, UnknownChannel> {
+                names
+                    .into_iter()
+                    .map(str::parse)
+                    .try_fold(Self::empty(), |channels, channel| Ok(channels | channel?))
+            }
+
+            /// Names of the set channels.
+            pub fn names(&self) -> Vec<&'static str> {
+                let mut names = Vec::new();
+                $(
+                    if self.contains(Self::$flag) {
+                        names.push($flag_name);
+                    }
+                )+
+                names
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = UnknownChannel;
+
+            /// Parse a single channel name.
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                match name {
+                    $($flag_name => Ok(Self::$flag),)+
+                    _ => Err(UnknownChannel(name.to_string())),
+                }
+            }
+        }
+
+        /// Channels are serialized as the list of their names.
+        #[cfg(feature = "serialize")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.names())
+            }
+        }
+
+        #[cfg(feature = "serialize")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let names = Vec::<std::borrow::Cow<str>>::deserialize(deserializer)?;
+                Self::from_names(names.iter().map(|name| &**name)).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+channels! {
+    /// Attributes of a [`Transform`](bevy::prelude::Transform) animated by a
+    /// curve.
+    pub struct TransformChannels {
+        const TRANSLATION = 1 << 0, "translation";
+        const ROTATION = 1 << 1, "rotation";
+        const SCALE = 1 << 2, "scale";
+    }
+}
+
+impl Default for TransformChannels {
+    fn default() -> Self {
+        Self::TRANSLATION | Self::ROTATION | Self::SCALE
+    }
+}
+
+/// How a curve interpolates the rotation of a
+/// [`Transform`](bevy::prelude::Transform).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum RotationInterpolation {
+    /// Spherical linear interpolation, at a constant angular speed.
+    #[default]
+    Slerp,
+    /// Normalized linear interpolation. Cheaper, but the angular speed isn't
+    /// constant.
+    Nlerp,
+}
+
+/// Settings of a curve animating a [`Transform`](bevy::prelude::Transform):
+/// its channels and how it interpolates rotations. Serialized as the list of
+/// channel names when the rotation is slerped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransformCurveChannels {
+    /// Attributes animated by the curve.
+    pub channels: TransformChannels,
+    /// How the rotation is interpolated, if animated.
+    pub rotation_interpolation: RotationInterpolation,
+}
+
+impl From<TransformChannels> for TransformCurveChannels {
+    fn from(channels: TransformChannels) -> Self {
+        Self {
+            channels,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl serde::Serialize for TransformCurveChannels {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        if self.rotation_interpolation == RotationInterpolation::Slerp {
+            return self.channels.serialize(serializer);
+        }
+        let mut settings = serializer.serialize_struct("TransformCurveChannels", 2)?;
+        settings.serialize_field("channels", &self.channels)?;
+        settings.serialize_field("rotation_interpolation", &self.rotation_interpolation)?;
+        settings.end()
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de> serde::Deserialize<'de> for TransformCurveChannels {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{value::SeqAccessDeserializer, Deserialize, MapAccess, SeqAccess, Visitor};
+
+        #[derive(serde::Deserialize)]
+        #[serde(field_identifier, rename_all = "snake_case")]
+        enum Field {
+            Channels,
+            RotationInterpolation,
+        }
+
+        struct SettingsVisitor;
+
+        impl<'de> Visitor<'de> for SettingsVisitor {
+            type Value = TransformCurveChannels;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a list of channel names or the settings of a curve")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                TransformChannels::deserialize(SeqAccessDeserializer::new(seq)).map(Into::into)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut settings = TransformCurveChannels::default();
+                while let Some(field) = map.next_key()? {
+                    match field {
+                        Field::Channels => settings.channels = map.next_value()?,
+                        Field::RotationInterpolation => {
+                            settings.rotation_interpolation = map.next_value()?
+                        }
+                    }
+                }
+                Ok(settings)
+            }
+        }
+
+        deserializer.deserialize_any(SettingsVisitor)
+    }
+}
+
+channels! {
+    /// Attributes of a [`Sprite`](bevy::prelude::Sprite) animated by a curve.
+    pub struct SpriteChannels {
+        const COLOR = 1 << 0, "color";
+        const FLIP_X = 1 << 1, "flip_x";
+        const FLIP_Y = 1 << 2, "flip_y";
+        const CUSTOM_SIZE = 1 << 3, "custom_size";
+        const ANCHOR = 1 << 4, "anchor";
+    }
+}
+
+impl Default for SpriteChannels {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+channels! {
+    /// Attributes of a [`TextureAtlasSprite`](bevy::prelude::TextureAtlasSprite)
+    /// animated by a curve.
+    pub struct TextureAtlasSpriteChannels {
+        const COLOR = 1 << 0, "color";
+        const INDEX = 1 << 1, "index";
+        const FLIP_X = 1 << 2, "flip_x";
+        const FLIP_Y = 1 << 3, "flip_y";
+        const CUSTOM_SIZE = 1 << 4, "custom_size";
+        const ANCHOR = 1 << 5, "anchor";
+    }
+}
+
+impl Default for TextureAtlasSpriteChannels {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+#[cfg(feature = "ui")]
+channels! {
+    /// Attributes of a [`Style`](bevy::prelude::Style) animated by a curve.
+    pub struct StyleChannels {
+        const POSITION = 1 << 0, "position";
+        const SIZE = 1 << 1, "size";
+        const MIN_SIZE = 1 << 2, "min_size";
+        const MAX_SIZE = 1 << 3, "max_size";
+        const MARGIN = 1 << 4, "margin";
+        const PADDING = 1 << 5, "padding";
+        const BORDER = 1 << 6, "border";
+    }
+}
+
+#[cfg(feature = "ui")]
+impl Default for StyleChannels {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+#[cfg(feature = "ui")]
+channels! {
+    /// Attributes of the sections of a [`Text`](bevy::prelude::Text) animated
+    /// by a curve.
+    pub struct TextChannels {
+        const COLOR = 1 << 0, "color";
+        const FONT_SIZE = 1 << 1, "font_size";
+    }
+}
+
+#[cfg(feature = "ui")]
+impl Default for TextChannels {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_channels() {
+        assert_eq!(
+            TransformChannels::from_names(["translation", "scale"]),
+            Ok(TransformChannels::TRANSLATION | TransformChannels::SCALE)
+        );
+        assert_eq!(
+            TextureAtlasSpriteChannels::from_names(["index", "flipx"]),
+            Err(UnknownChannel("flipx".to_string()))
+        );
+        assert_eq!(
+            TransformChannels::default().names(),
+            ["translation", "rotation", "scale"]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::utils::HashMap;
+
+use crate::{find_segment, segment_progress, KeyframeEntityPath};
+
+/// Segments the curves of a clip were last sampled in, by path, so that a
+/// player doesn't search the keyframes of its curves every frame.
+#[derive(Default)]
+pub(crate) struct KeyframeCursors {
+    segments: HashMap<KeyframeEntityPath, Vec<usize>>,
+}
+
+impl KeyframeCursors {
+    /// Segments of the `curves` curves of `path`, out of the curves until
+    /// they're first sampled.
+    pub(crate) fn segments(&mut self, path: &KeyframeEntityPath, curves: usize) -> &mut [usize] {
+        if !self.segments.contains_key(path) {
+            self.segments.insert(path.clone(), Vec::new());
+        }
+        let segments = self.segments.get_mut(path).unwrap();
+        // The clip may have been reloaded with other curves
+        segments.resize(curves, usize::MAX);
+        segments
+    }
+}
+
+/// Like [`find_segment`], checking first the segment `hint` and the ones next
+/// to it, as consecutive frames usually stay in the same segment or move to
+/// an adjacent one. The whole curve is searched otherwise, e.g. after a seek.
+pub(crate) fn find_segment_from(
+    keyframe_timestamps: &[f32],
+    elapsed: f32,
+    hint: usize,
+) -> Option<(usize, f32)> {
+    let last = keyframe_timestamps.len().checked_sub(2)?;
+    for segment in [hint, hint.wrapping_add(1), hint.wrapping_sub(1)] {
+        if segment > last {
+            continue;
+        }
+        let ts_start = keyframe_timestamps[segment];
+        let ts_end = keyframe_timestamps[segment + 1];
+        // The last segment includes its end, so the final keyframe can be
+        // reached
+        if ts_start <= elapsed && (elapsed < ts_end || (segment == last && elapsed <= ts_end)) {
+            return Some((
+                segment,
+                segment_progress(keyframe_timestamps, segment, elapsed),
+            ));
+        }
+    }
+    find_segment(keyframe_timestamps, elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_matches_search() {
+        let keyframe_timestamps = [0.0, 0.5, 1.0, 2.0, 4.0];
+        for hint in [0, 1, 2, 3, 7, usize::MAX] {
+            for elapsed in [-1.0, 0.0, 0.25, 0.5, 0.9, 1.0, 1.5, 2.0, 3.0, 4.0, 5.0] {
+                assert_eq!(
+                    find_segment_from(&keyframe_timestamps, elapsed, hint),
+                    find_segment(&keyframe_timestamps, elapsed),
+                    "hint {} at {}",
+                    hint,
+                    elapsed
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// Easing function applied to the interpolation scalar of a segment, before
+/// [`Lerp::lerp`](crate::lerp::Lerp::lerp) is called.
+///
+/// See <https://easings.net> for a preview of each of the curves.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ease {
+    #[default]
+    Linear,
+    /// Hold the value of the segment start until the next keyframe.
+    Step,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    QuintIn,
+    QuintOut,
+    QuintInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    /// Timing function of CSS `cubic-bezier(x1, y1, x2, y2)`, a cubic Bézier
+    /// from `(0, 0)` to `(1, 1)` with the two given control points. `x1` and
+    /// `x2` should be in `[0, 1]`.
+    CubicBezier(f32, f32, f32, f32),
+    /// User provided easing function, mapping `[0, 1]` to the scalar to use.
+    /// It can't be serialized.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    Custom(fn(f32) -> f32),
+}
+
+impl Ease {
+    /// Apply the easing function to `t`, the progress in the segment between
+    /// `0.0` and `1.0`.
+    pub fn apply(&self, t: f32) -> f32 {
+        const BACK: f32 = 1.70158;
+        const BACK_IN_OUT: f32 = BACK * 1.525;
+        const ELASTIC: f32 = 2.0 * PI / 3.0;
+        const ELASTIC_IN_OUT: f32 = 2.0 * PI / 4.5;
+
+        match self {
+            Ease::Linear => t,
+            Ease::Step => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::QuadInOut => in_out(t, |t| t * t),
+            Ease::CubicIn => t.powi(3),
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::CubicInOut => in_out(t, |t| t.powi(3)),
+            Ease::QuartIn => t.powi(4),
+            Ease::QuartOut => 1.0 - (1.0 - t).powi(4),
+            Ease::QuartInOut => in_out(t, |t| t.powi(4)),
+            Ease::QuintIn => t.powi(5),
+            Ease::QuintOut => 1.0 - (1.0 - t).powi(5),
+            Ease::QuintInOut => in_out(t, |t| t.powi(5)),
+            Ease::SineIn => 1.0 - (t * FRAC_PI_2).cos(),
+            Ease::SineOut => (t * FRAC_PI_2).sin(),
+            Ease::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Ease::ExpoIn => expo_in(t),
+            Ease::ExpoOut => 1.0 - expo_in(1.0 - t),
+            Ease::ExpoInOut => in_out(t, expo_in),
+            Ease::CircIn => circ_in(t),
+            Ease::CircOut => 1.0 - circ_in(1.0 - t),
+            Ease::CircInOut => in_out(t, circ_in),
+            Ease::BackIn => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Ease::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Ease::BackInOut => in_out(t, |t| (BACK_IN_OUT + 1.0) * t.powi(3) - BACK_IN_OUT * t * t),
+            Ease::ElasticIn => elastic_in(t, ELASTIC, 10.0, 10.75),
+            Ease::ElasticOut => 1.0 - elastic_in(1.0 - t, ELASTIC, 10.0, 10.75),
+            Ease::ElasticInOut => in_out(t, |t| elastic_in(t, ELASTIC_IN_OUT, 10.0, 11.125)),
+            Ease::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Ease::BounceOut => bounce_out(t),
+            Ease::BounceInOut => in_out(t, |t| 1.0 - bounce_out(1.0 - t)),
+            Ease::CubicBezier(x1, y1, x2, y2) => cubic_bezier(t, *x1, *y1, *x2, *y2),
+            Ease::Custom(f) => f(t),
+        }
+    }
+}
+
+/// Build the in-out variant of an ease-in function: the first half is the
+/// ease-in, the second half is its mirror.
+fn in_out(t: f32, ease_in: impl Fn(f32) -> f32) -> f32 {
+    if t < 0.5 {
+        ease_in(2.0 * t) / 2.0
+    } else {
+        1.0 - ease_in(2.0 - 2.0 * t) / 2.0
+    }
+}
+
+fn expo_in(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else {
+        2f32.powf(10.0 * t - 10.0)
+    }
+}
+
+fn circ_in(t: f32) -> f32 {
+    1.0 - (1.0 - t * t).max(0.0).sqrt()
+}
+
+fn elastic_in(t: f32, period: f32, amplitude: f32, shift: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        -(2f32.powf(amplitude * t - amplitude)) * ((t * amplitude - shift) * period).sin()
+    }
+}
+
+/// Coordinate of a cubic Bézier from `0` to `1` with the control points `p1`
+/// and `p2`, at the parameter `s`.
+fn bezier(s: f32, p1: f32, p2: f32) -> f32 {
+    let r = 1.0 - s;
+    3.0 * r * r * s * p1 + 3.0 * r * s * s * p2 + s * s * s
+}
+
+fn cubic_bezier(t: f32, x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return t.clamp(0.0, 1.0);
+    }
+    // Find the parameter for which the curve reaches `t` on the x axis, with
+    // Newton's method and a bisection fallback as browsers do
+    let mut s = t;
+    for _ in 0..8 {
+        let error = bezier(s, x1, x2) - t;
+        if error.abs() < 1e-6 {
+            return bezier(s, y1, y2);
+        }
+        let r = 1.0 - s;
+        let slope = 3.0 * r * r * x1 + 6.0 * r * s * (x2 - x1) + 3.0 * s * s * (1.0 - x2);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s -= error / slope;
+    }
+    let (mut low, mut high) = (0.0, 1.0);
+    s = t;
+    for _ in 0..32 {
+        let x = bezier(s, x1, x2);
+        if (x - t).abs() < 1e-6 {
+            break;
+        }
+        if x < t {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+    bezier(s, y1, y2)
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASES: [Ease; 32] = [
+        Ease::Linear,
+        Ease::Step,
+        Ease::QuadIn,
+        Ease::QuadOut,
+        Ease::QuadInOut,
+        Ease::CubicIn,
+        Ease::CubicOut,
+        Ease::CubicInOut,
+        Ease::QuartIn,
+        Ease::QuartOut,
+        Ease::QuartInOut,
+        Ease::QuintIn,
+        Ease::QuintOut,
+        Ease::QuintInOut,
+        Ease::SineIn,
+        Ease::SineOut,
+        Ease::SineInOut,
+        Ease::ExpoIn,
+        Ease::ExpoOut,
+        Ease::ExpoInOut,
+        Ease::CircIn,
+        Ease::CircOut,
+        Ease::CircInOut,
+        Ease::BackIn,
+        Ease::BackOut,
+        Ease::BackInOut,
+        Ease::ElasticIn,
+        Ease::ElasticOut,
+        Ease::ElasticInOut,
+        Ease::BounceIn,
+        Ease::BounceOut,
+        Ease::BounceInOut,
+    ];
+
+    #[test]
+    fn eases_start_at_zero_and_end_at_one() {
+        for ease in EASES {
+            assert!(ease.apply(0.0).abs() < 1e-3, "{:?} at 0", ease);
+            assert!((ease.apply(1.0) - 1.0).abs() < 1e-3, "{:?} at 1", ease);
+        }
+    }
+
+    #[test]
+    fn in_out_eases_are_symmetric() {
+        for ease in [
+            Ease::QuadInOut,
+            Ease::CubicInOut,
+            Ease::SineInOut,
+            Ease::BounceInOut,
+        ] {
+            assert!((ease.apply(0.5) - 0.5).abs() < 1e-5, "{:?}", ease);
+            assert!(
+                (ease.apply(0.25) + ease.apply(0.75) - 1.0).abs() < 1e-5,
+                "{:?}",
+                ease
+            );
+        }
+    }
+
+    #[test]
+    fn cubic_bezier_matches_css_keywords() {
+        // CSS `linear` and `ease-in-out`
+        assert!((Ease::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3) - 0.3).abs() < 1e-4);
+        let ease_in_out = Ease::CubicBezier(0.42, 0.0, 0.58, 1.0);
+        assert!((ease_in_out.apply(0.5) - 0.5).abs() < 1e-4);
+        assert!(ease_in_out.apply(0.25) < 0.25);
+        assert!(ease_in_out.apply(0.75) > 0.75);
+    }
+
+    #[test]
+    fn custom_ease() {
+        assert_eq!(Ease::Custom(|t| t * 0.5).apply(1.0), 0.5);
+        assert_eq!(Ease::Step.apply(0.99), 0.0);
+    }
+}
@@ -0,0 +1,59 @@
+use std::{error::Error, fmt};
+
+/// Problem found when validating a
+/// [`KeyframeVariableCurve`](crate::KeyframeVariableCurve).
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyframeError {
+    /// There isn't one keyframe per timestamp.
+    LengthMismatch { timestamps: usize, keyframes: usize },
+    /// There isn't one pair of Bézier handles per keyframe.
+    HandlesMismatch { keyframes: usize, handles: usize },
+    /// There isn't one mode per keyframe.
+    ModesMismatch { keyframes: usize, modes: usize },
+    /// The timestamp at `index` is NaN or infinite.
+    InvalidTimestamp { index: usize, timestamp: f32 },
+    /// The timestamp at `index` is the same as the previous one.
+    DuplicateTimestamp { index: usize, timestamp: f32 },
+    /// The timestamp at `index` is before the previous one.
+    UnsortedTimestamp { index: usize, timestamp: f32 },
+}
+
+impl fmt::Display for KeyframeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyframeError::LengthMismatch {
+                timestamps,
+                keyframes,
+            } => write!(
+                f,
+                "curve has {} keyframe timestamps but {} keyframes",
+                timestamps, keyframes
+            ),
+            KeyframeError::HandlesMismatch { keyframes, handles } => write!(
+                f,
+                "curve has {} keyframes but {} Bézier handles",
+                keyframes, handles
+            ),
+            KeyframeError::ModesMismatch { keyframes, modes } => write!(
+                f,
+                "curve has {} keyframes but {} keyframe modes",
+                keyframes, modes
+            ),
+            KeyframeError::InvalidTimestamp { index, timestamp } => {
+                write!(f, "keyframe timestamp {} is {}", index, timestamp)
+            }
+            KeyframeError::DuplicateTimestamp { index, timestamp } => write!(
+                f,
+                "keyframe timestamp {} is {}, the same as the previous one",
+                index, timestamp
+            ),
+            KeyframeError::UnsortedTimestamp { index, timestamp } => write!(
+                f,
+                "keyframe timestamp {} is {}, before the previous one",
+                index, timestamp
+            ),
+        }
+    }
+}
+
+impl Error for KeyframeError {}
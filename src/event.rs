@@ -0,0 +1,41 @@
+use bevy::{asset::HandleId, prelude::*};
+
+/// Event sent when a player starts playing a clip.
+#[derive(Clone, Debug)]
+pub struct AnimationStarted {
+    /// Entity of the player.
+    pub entity: Entity,
+    /// Clip being played.
+    pub clip: HandleId,
+}
+
+/// Event sent each time a repeating player loops over its clip.
+#[derive(Clone, Debug)]
+pub struct AnimationLooped {
+    /// Entity of the player.
+    pub entity: Entity,
+    /// Clip being played.
+    pub clip: HandleId,
+    /// Number of loops played since the clip started, including this one.
+    pub loop_count: u32,
+}
+
+/// Event sent when a player that doesn't repeat reaches the end of its clip,
+/// or its start when playing backward.
+#[derive(Clone, Debug)]
+pub struct AnimationFinished {
+    /// Entity of the player.
+    pub entity: Entity,
+    /// Clip that was played.
+    pub clip: HandleId,
+}
+
+/// Event sent when a [`Tween`](crate::tween::Tween) reaches its target and
+/// removes itself.
+#[derive(Clone, Debug)]
+pub struct TweenFinished {
+    /// Entity of the tween.
+    pub entity: Entity,
+    /// Data given to the tween.
+    pub user_data: u64,
+}
@@ -1,45 +1,82 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::Uuid};
 
-use crate::{lerp::Lerp, Keyframe};
+use crate::{
+    channels::{
+        RotationInterpolation, SpriteChannels, TextureAtlasSpriteChannels, TransformChannels,
+        TransformCurveChannels,
+    },
+    lerp::{Animatable, Lerp},
+    Keyframe, KeyframeClipUuid,
+};
 
 impl Lerp<Transform> for Keyframe<Transform> {
+    type Channels = TransformCurveChannels;
+
     fn lerp(
         &self,
         other: &Self,
         scalar: f32,
         target: &Transform,
-        options: &Option<Vec<String>>,
+        settings: &TransformCurveChannels,
     ) -> Self {
-        match options {
-            Some(ops) => {
-                let mut transform = Transform { ..target.clone() };
-                for op in ops {
-                    match op.as_str() {
-                        "translation" => {
-                            transform.translation =
-                                self.0.translation.lerp(other.0.translation, scalar);
-                        }
-                        "scale" => {
-                            transform.scale = self.0.scale.lerp(other.0.scale, scalar);
-                        }
-                        "rotation" => {
-                            transform.rotation =
-                                self.0.rotation.normalize().lerp(other.0.rotation, scalar);
-                        }
-                        _ => {}
-                    }
-                }
-                Keyframe(transform)
-            }
-            None => Keyframe(Transform {
-                translation: self.0.translation.lerp(other.0.translation, scalar),
-                scale: self.0.scale.lerp(other.0.scale, scalar),
-                rotation: self.0.rotation.normalize().lerp(other.0.rotation, scalar),
-            }),
+        let channels = settings.channels;
+        let mut transform = *target;
+        if channels.contains(TransformChannels::TRANSLATION) {
+            transform.translation = self.0.translation.lerp(other.0.translation, scalar);
+        }
+        if channels.contains(TransformChannels::SCALE) {
+            transform.scale = self.0.scale.lerp(other.0.scale, scalar);
+        }
+        if channels.contains(TransformChannels::ROTATION) {
+            transform.rotation = interpolate_rotation(
+                self.0.rotation,
+                other.0.rotation,
+                scalar,
+                settings.rotation_interpolation == RotationInterpolation::Nlerp,
+            );
+        }
+        Keyframe(transform)
+    }
+
+    fn add_to(&self, base: &Transform, settings: &TransformCurveChannels) -> Self {
+        let channels = settings.channels;
+        let mut transform = *base;
+        if channels.contains(TransformChannels::TRANSLATION) {
+            transform.translation += self.0.translation;
+        }
+        if channels.contains(TransformChannels::SCALE) {
+            transform.scale *= self.0.scale;
         }
+        if channels.contains(TransformChannels::ROTATION) {
+            transform.rotation = (transform.rotation * self.0.rotation).normalize();
+        }
+        Keyframe(transform)
     }
 }
 
+/// Interpolate between two rotations along the shortest path, with a slerp or
+/// a cheaper normalized lerp. The result is always normalized.
+pub(crate) fn interpolate_rotation(from: Quat, to: Quat, scalar: f32, nlerp: bool) -> Quat {
+    let from = from.normalize();
+    let mut to = to.normalize();
+    let mut dot = from.dot(to);
+    // Quaternions in opposite hemispheres represent the same rotation, flip
+    // one of them to take the short way
+    if dot < 0.0 {
+        to = -to;
+        dot = -dot;
+    }
+    // Close rotations are interpolated linearly to avoid dividing by ~0
+    if nlerp || dot > 0.9995 {
+        return (from * (1.0 - scalar) + to * scalar).normalize();
+    }
+    let theta = dot.acos();
+    let sin_theta = theta.sin();
+    let from_scale = ((1.0 - scalar) * theta).sin() / sin_theta;
+    let to_scale = (scalar * theta).sin() / sin_theta;
+    (from * from_scale + to * to_scale).normalize()
+}
+
 impl Keyframe<Transform> {
     pub fn translation(values: Vec<Vec3>) -> Vec<Keyframe<Transform>> {
         values
@@ -79,25 +116,69 @@ impl Keyframe<Transform> {
 }
 
 impl Lerp<Sprite> for Keyframe<Sprite> {
-    fn lerp(&self, other: &Self, scalar: f32, _: &Sprite, _: &Option<Vec<String>>) -> Self {
-        Keyframe(Sprite {
-            custom_size: match (self.0.custom_size, other.0.custom_size) {
-                (None, None) => None,
-                (None, Some(b)) => Some(b),
-                (Some(a), None) => Some(a),
-                (Some(a), Some(b)) => Some(a.lerp(b, scalar)),
-            },
-            #[cfg(feature = "render")]
-            color: Keyframe(self.0.color)
-                .lerp(&Keyframe(other.0.color), scalar, &self.0.color, &None)
-                .0,
-            ..other.0.clone()
-        })
+    type Channels = SpriteChannels;
+
+    fn lerp(&self, other: &Self, scalar: f32, target: &Sprite, channels: &SpriteChannels) -> Self {
+        let mut sprite = target.clone();
+        #[cfg(feature = "render")]
+        if channels.contains(SpriteChannels::COLOR) {
+            sprite.color = lerp_color(self.0.color, other.0.color, scalar);
+        }
+        if channels.contains(SpriteChannels::FLIP_X) {
+            sprite.flip_x = other.0.flip_x;
+        }
+        if channels.contains(SpriteChannels::FLIP_Y) {
+            sprite.flip_y = other.0.flip_y;
+        }
+        if channels.contains(SpriteChannels::CUSTOM_SIZE) {
+            sprite.custom_size = lerp_size(self.0.custom_size, other.0.custom_size, scalar);
+        }
+        if channels.contains(SpriteChannels::ANCHOR) {
+            sprite.anchor = other.0.anchor.clone();
+        }
+        Keyframe(sprite)
+    }
+
+    fn add_to(&self, base: &Sprite, channels: &SpriteChannels) -> Self {
+        // Only the color is added, without its alpha, the other attributes
+        // are overridden
+        let overridden = *channels - SpriteChannels::COLOR;
+        let mut sprite = self.lerp(self, 1.0, base, &overridden).0;
+        #[cfg(feature = "render")]
+        if channels.contains(SpriteChannels::COLOR) {
+            sprite.color = add_color(base.color, self.0.color);
+        }
+        Keyframe(sprite)
+    }
+}
+
+/// Interpolate between two optional sizes, keeping the one that is set if
+/// only one of them is.
+fn lerp_size(from: Option<Vec2>, to: Option<Vec2>, scalar: f32) -> Option<Vec2> {
+    match (from, to) {
+        (None, None) => None,
+        (None, Some(b)) => Some(b),
+        (Some(a), None) => Some(a),
+        (Some(a), Some(b)) => Some(a.lerp(b, scalar)),
     }
 }
 
+/// Add the color channels of `delta` to `base`, keeping the alpha of `base`.
+pub(crate) fn add_color(base: Color, delta: Color) -> Color {
+    let mut color = base + delta;
+    color.set_a(base.a());
+    color
+}
+
+#[cfg(feature = "render")]
+fn lerp_color(from: Color, to: Color, scalar: f32) -> Color {
+    Keyframe(from).lerp(&Keyframe(to), scalar, &from, &()).0
+}
+
 impl Lerp<Color> for Keyframe<Color> {
-    fn lerp(&self, other: &Self, scalar: f32, _: &Color, _: &Option<Vec<String>>) -> Self {
+    type Channels = ();
+
+    fn lerp(&self, other: &Self, scalar: f32, _: &Color, _: &()) -> Self {
         let color = match (self.0, other.0) {
             (
                 Color::Rgba {
@@ -113,10 +194,10 @@ impl Lerp<Color> for Keyframe<Color> {
                     alpha: alphao,
                 },
             ) => Color::Rgba {
-                red: red + (redo + (red * -1.0)) * scalar,
-                green: green + (greeno + (green * -1.0)) * scalar,
-                blue: blue + (blueo + (blue * -1.0)) * scalar,
-                alpha: alpha + (alphao + (alpha * -1.0)) * scalar,
+                red: red + (redo - red) * scalar,
+                green: green + (greeno - green) * scalar,
+                blue: blue + (blueo - blue) * scalar,
+                alpha: alpha + (alphao - alpha) * scalar,
             },
             (
                 Color::RgbaLinear {
@@ -132,10 +213,10 @@ impl Lerp<Color> for Keyframe<Color> {
                     alpha: alphao,
                 },
             ) => Color::RgbaLinear {
-                red: red + (redo + (red * -1.0)) * scalar,
-                green: green + (greeno + (green * -1.0)) * scalar,
-                blue: blue + (blueo + (blue * -1.0)) * scalar,
-                alpha: alpha + (alphao + (alpha * -1.0)) * scalar,
+                red: red + (redo - red) * scalar,
+                green: green + (greeno - green) * scalar,
+                blue: blue + (blueo - blue) * scalar,
+                alpha: alpha + (alphao - alpha) * scalar,
             },
             (
                 Color::Hsla {
@@ -151,55 +232,66 @@ impl Lerp<Color> for Keyframe<Color> {
                     alpha: alphao,
                 },
             ) => Color::Hsla {
-                hue: hue + (hueo + (hue * -1.0)) * scalar,
-                saturation: saturation + (saturationo + (saturation * -1.0)) * scalar,
-                lightness: lightness + (lightnesso + (lightness * -1.0)) * scalar,
-                alpha: alpha + (alphao + (alpha * -1.0)) * scalar,
+                hue: hue + (hueo - hue) * scalar,
+                saturation: saturation + (saturationo - saturation) * scalar,
+                lightness: lightness + (lightnesso - lightness) * scalar,
+                alpha: alpha + (alphao - alpha) * scalar,
             },
             _ => self.0 + (other.0 + (self.0 * -1.)) * scalar,
         };
         Keyframe(color)
     }
+
+    /// Only the color channels are added, the alpha of `base` is kept so
+    /// that offsets don't change the opacity.
+    fn add_to(&self, base: &Color, _: &()) -> Self {
+        Keyframe(add_color(*base, self.0))
+    }
 }
 
 impl Lerp<TextureAtlasSprite> for Keyframe<TextureAtlasSprite> {
+    type Channels = TextureAtlasSpriteChannels;
+
     fn lerp(
         &self,
         other: &Self,
         scalar: f32,
         target: &TextureAtlasSprite,
-        options: &Option<Vec<String>>,
+        channels: &TextureAtlasSpriteChannels,
     ) -> Self {
-        match options {
-            Some(ops) => {
-                let mut sprite = TextureAtlasSprite { ..target.clone() };
-                for o in ops {
-                    match o.as_str() {
-                        "index" => sprite.index = other.0.index,
-                        "custom_size" => sprite.custom_size = other.0.custom_size,
-                        "flip_x" => sprite.flip_x = other.0.flip_x,
-                        "flip_y" => sprite.flip_y = other.0.flip_y,
-                        "anchor" => sprite.anchor = other.0.anchor.clone(),
-                        "color" => sprite.color = other.0.color,
-                        _ => {}
-                    }
-                }
-                Keyframe(sprite)
-            }
-            None => Keyframe(TextureAtlasSprite {
-                custom_size: match (self.0.custom_size, other.0.custom_size) {
-                    (None, None) => None,
-                    (None, Some(b)) => Some(b),
-                    (Some(a), None) => Some(a),
-                    (Some(a), Some(b)) => Some(a.lerp(b, scalar)),
-                },
-                #[cfg(feature = "render")]
-                color: Keyframe(self.0.color)
-                    .lerp(&Keyframe(other.0.color), scalar, &self.0.color, &None)
-                    .0,
-                ..other.0.clone()
-            }),
+        let mut sprite = target.clone();
+        #[cfg(feature = "render")]
+        if channels.contains(TextureAtlasSpriteChannels::COLOR) {
+            sprite.color = lerp_color(self.0.color, other.0.color, scalar);
+        }
+        if channels.contains(TextureAtlasSpriteChannels::INDEX) {
+            sprite.index = other.0.index;
+        }
+        if channels.contains(TextureAtlasSpriteChannels::FLIP_X) {
+            sprite.flip_x = other.0.flip_x;
+        }
+        if channels.contains(TextureAtlasSpriteChannels::FLIP_Y) {
+            sprite.flip_y = other.0.flip_y;
+        }
+        if channels.contains(TextureAtlasSpriteChannels::CUSTOM_SIZE) {
+            sprite.custom_size = lerp_size(self.0.custom_size, other.0.custom_size, scalar);
+        }
+        if channels.contains(TextureAtlasSpriteChannels::ANCHOR) {
+            sprite.anchor = other.0.anchor.clone();
+        }
+        Keyframe(sprite)
+    }
+
+    fn add_to(&self, base: &TextureAtlasSprite, channels: &TextureAtlasSpriteChannels) -> Self {
+        // Only the color is added, without its alpha, the other attributes
+        // are overridden
+        let overridden = *channels - TextureAtlasSpriteChannels::COLOR;
+        let mut sprite = self.lerp(self, 1.0, base, &overridden).0;
+        #[cfg(feature = "render")]
+        if channels.contains(TextureAtlasSpriteChannels::COLOR) {
+            sprite.color = add_color(base.color, self.0.color);
         }
+        Keyframe(sprite)
     }
 }
 
@@ -218,7 +310,9 @@ impl Keyframe<TextureAtlasSprite> {
 }
 
 impl Lerp<Handle<Image>> for Keyframe<Handle<Image>> {
-    fn lerp(&self, other: &Self, _: f32, _: &Handle<Image>, _: &Option<Vec<String>>) -> Self {
+    type Channels = ();
+
+    fn lerp(&self, other: &Self, _: f32, _: &Handle<Image>, _: &()) -> Self {
         Keyframe(other.0.clone())
     }
 }
@@ -232,3 +326,189 @@ impl Keyframe<Handle<Image>> {
         images
     }
 }
+
+impl KeyframeClipUuid for Transform {
+    const CLIP_UUID: Uuid = Uuid::from_u128(0x6d8f4b0e_5c2a_4f6e_9b1d_3a7c2e9f8d41);
+}
+
+impl KeyframeClipUuid for Sprite {
+    const CLIP_UUID: Uuid = Uuid::from_u128(0x2b9e7c13_8a4d_4c51_a6f0_91d3e5b7c028);
+}
+
+impl KeyframeClipUuid for TextureAtlasSprite {
+    const CLIP_UUID: Uuid = Uuid::from_u128(0xc4a1f2d9_3e6b_4d87_8f25_7b0e6a9d1c53);
+}
+
+impl KeyframeClipUuid for Handle<Image> {
+    const CLIP_UUID: Uuid = Uuid::from_u128(0x9f3d5a27_b1c8_4e02_95a6_d4f8e2c7b316);
+}
+
+impl Animatable for f32 {
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn scale(&self, scalar: f32) -> Self {
+        self * scalar
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        (self - other).abs()
+    }
+}
+
+impl Animatable for Vec2 {
+    fn add(&self, other: &Self) -> Self {
+        *self + *other
+    }
+
+    fn scale(&self, scalar: f32) -> Self {
+        *self * scalar
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec2::distance(*self, *other)
+    }
+}
+
+impl Animatable for Vec3 {
+    fn add(&self, other: &Self) -> Self {
+        *self + *other
+    }
+
+    fn scale(&self, scalar: f32) -> Self {
+        *self * scalar
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec3::distance(*self, *other)
+    }
+}
+
+impl Animatable for Quat {
+    fn add(&self, other: &Self) -> Self {
+        *self + *other
+    }
+
+    fn scale(&self, scalar: f32) -> Self {
+        *self * scalar
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        (*self - *other).length()
+    }
+}
+
+impl Animatable for Color {
+    fn add(&self, other: &Self) -> Self {
+        *self + *other
+    }
+
+    fn scale(&self, scalar: f32) -> Self {
+        // `Mul<f32>` leaves the alpha untouched
+        *self * Vec4::splat(scalar)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec4::from(self.as_rgba_f32()).distance(Vec4::from(other.as_rgba_f32()))
+    }
+}
+
+impl Animatable for Transform {
+    fn add(&self, other: &Self) -> Self {
+        Transform {
+            translation: self.translation.add(&other.translation),
+            rotation: self.rotation.add(&other.rotation),
+            scale: self.scale.add(&other.scale),
+        }
+    }
+
+    fn scale(&self, scalar: f32) -> Self {
+        Transform {
+            translation: self.translation.scale(scalar),
+            rotation: self.rotation.scale(scalar),
+            scale: self.scale.scale(scalar),
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        let translation = Animatable::distance(&self.translation, &other.translation);
+        let rotation = Animatable::distance(&self.rotation, &other.rotation);
+        let scale = Animatable::distance(&self.scale, &other.scale);
+        (translation * translation + rotation * rotation + scale * scale).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+
+    #[test]
+    fn rotations_slerp_at_constant_speed() {
+        let from = Quat::IDENTITY;
+        let to = Quat::from_rotation_y(FRAC_PI_2);
+        for step in 0..=4 {
+            let scalar = step as f32 / 4.0;
+            let rotation = interpolate_rotation(from, to, scalar, false);
+            let expected = Quat::from_rotation_y(FRAC_PI_2 * scalar);
+            assert!(rotation.angle_between(expected) < 1e-3);
+            assert!(rotation.is_normalized());
+        }
+    }
+
+    #[test]
+    fn rotations_take_the_shortest_path() {
+        let from = Quat::from_rotation_y(0.1);
+        // Same rotation as `0.3` radians, in the opposite hemisphere
+        let to = -Quat::from_rotation_y(0.3);
+        for nlerp in [false, true] {
+            let rotation = interpolate_rotation(from, to, 0.5, nlerp);
+            assert!(rotation.angle_between(Quat::from_rotation_y(0.2)) < 1e-3);
+            assert!(rotation.is_normalized());
+        }
+        // Half way between opposite rotations around the same axis
+        let rotation =
+            interpolate_rotation(Quat::IDENTITY, Quat::from_rotation_y(PI * 0.99), 0.5, false);
+        assert!(rotation.angle_between(Quat::from_rotation_y(PI * 0.495)) < 1e-3);
+    }
+
+    #[test]
+    fn nlerp_is_selected_per_curve() {
+        let from = Keyframe(Transform::from_rotation(Quat::IDENTITY));
+        let to = Keyframe(Transform::from_rotation(Quat::from_rotation_y(FRAC_PI_2)));
+        let channels = TransformCurveChannels {
+            channels: TransformChannels::ROTATION,
+            rotation_interpolation: RotationInterpolation::Nlerp,
+        };
+        let rotation = from
+            .lerp(&to, 0.25, &Transform::default(), &channels)
+            .0
+            .rotation;
+        assert!(rotation.is_normalized());
+        assert_eq!(
+            rotation,
+            interpolate_rotation(from.0.rotation, to.0.rotation, 0.25, true)
+        );
+        assert!(rotation.angle_between(Quat::from_rotation_y(FRAC_PI_2 * 0.25)) > 1e-4);
+    }
+
+    #[test]
+    fn color_offsets_keep_the_alpha() {
+        let base = Color::rgba(0.5, 0.5, 0.5, 0.25);
+        let color = Keyframe(Color::rgb(0.25, 0.0, 0.0)).add_to(&base, &()).0;
+        assert_eq!(color, Color::rgba(0.75, 0.5, 0.5, 0.25));
+
+        let sprite = Sprite {
+            color: base,
+            ..Default::default()
+        };
+        let offset = Keyframe(Sprite {
+            color: Color::rgb(0.0, 0.25, 0.0),
+            ..Default::default()
+        });
+        let sprite = offset.add_to(&sprite, &SpriteChannels::COLOR).0;
+        assert_eq!(sprite.color, Color::rgba(0.5, 0.75, 0.5, 0.25));
+    }
+}
@@ -0,0 +1,49 @@
+use bevy::{asset::Asset, prelude::*};
+
+use crate::{lerp::Lerp, repeat::RepeatMode, Keyframe, KeyframeAnimationClip, KeyframeEntityPath};
+
+/// Clip played by a [`KeyframeAnimationPlayer`](crate::KeyframeAnimationPlayer)
+/// on top of its main clip and of the previous layers, e.g. an upper body
+/// wave over the locomotion.
+pub struct KeyframeAnimationLayer<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    /// Clip played by the layer.
+    pub animation_clip: Handle<KeyframeAnimationClip<T>>,
+    /// How much the layer overrides the layers below, between `0.0` and
+    /// `1.0`.
+    pub weight: f32,
+    /// Entities animated by the layer, with all their descendants. The layer
+    /// animates every entity of its clip when empty.
+    pub mask: Vec<KeyframeEntityPath>,
+    /// How the layer repeats its clip.
+    pub repeat_mode: RepeatMode,
+    /// Speed of the layer playback.
+    pub speed: f32,
+    /// Time elapsed playing the layer.
+    pub elapsed: f32,
+}
+
+impl<T> KeyframeAnimationLayer<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    pub fn new(animation_clip: Handle<KeyframeAnimationClip<T>>) -> Self {
+        Self {
+            animation_clip,
+            weight: 1.0,
+            mask: Vec::new(),
+            repeat_mode: RepeatMode::Once,
+            speed: 1.0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Whether the layer animates the entity at `path`.
+    pub fn is_masked(&self, path: &KeyframeEntityPath) -> bool {
+        !self.mask.is_empty() && !self.mask.iter().any(|prefix| path.starts_with(prefix))
+    }
+}
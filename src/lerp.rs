@@ -1,8 +1,36 @@
 pub trait Lerp<T> {
+    /// Attributes of `T` that a curve can select to animate. Its default value
+    /// should animate every attribute.
+    type Channels: Clone + Default + Send + Sync + 'static;
+
     /// The scaling type for linear interpolation.
 
     /// Given `self` and another point `other`, return a point on a line running
     /// between the two that is `scalar` fraction of the distance between
-    /// the two points.
-    fn lerp(&self, other: &Self, scalar: f32, target: &T, options: &Option<Vec<String>>) -> Self;
+    /// the two points. Only the `channels` are interpolated, the other
+    /// attributes are taken from `target`.
+    #[allow(clippy::empty_line_after_doc_comments)]
+    fn lerp(&self, other: &Self, scalar: f32, target: &T, channels: &Self::Channels) -> Self;
+
+    /// Apply `self` as a delta on top of `base`, for additive clips. Only the
+    /// `channels` are changed, the other attributes are taken from `base`.
+    /// Overrides `base` by default, for types whose values can't be added.
+    fn add_to(&self, base: &T, channels: &Self::Channels) -> Self
+    where
+        Self: Sized,
+    {
+        self.lerp(self, 1.0, base, channels)
+    }
+}
+
+/// Arithmetic on values, used to build splines through keyframes.
+pub trait Animatable {
+    /// Component-wise sum of `self` and `other`.
+    fn add(&self, other: &Self) -> Self;
+
+    /// Component-wise product of `self` by `scalar`.
+    fn scale(&self, scalar: f32) -> Self;
+
+    /// Euclidean distance between `self` and `other`.
+    fn distance(&self, other: &Self) -> f32;
 }
@@ -1,38 +1,384 @@
+mod blend;
+mod builder;
+
+mod channels;
+mod cursor;
+
+mod ease;
+mod error;
+
+mod event;
 mod implemented;
+mod layer;
 mod lerp;
+#[cfg(feature = "serialize")]
+mod loader;
+mod marker;
+mod property;
+mod queue;
+mod repeat;
+mod spline;
+mod state;
+mod target;
+mod tween;
+#[cfg(feature = "ui")]
+mod ui;
 
 pub mod prelude {
-    pub use crate::{lerp::Lerp, *};
+    pub use crate::{
+        blend::{keyframe_blend_tree_player, KeyframeBlendTree, KeyframeBlendTreePlayer},
+        builder::{KeyframeClipBuilder, KeyframeCurveBuilder},
+        channels::{
+            RotationInterpolation, SpriteChannels, TextureAtlasSpriteChannels, TransformChannels,
+            TransformCurveChannels, UnknownChannel,
+        },
+        ease::Ease,
+        error::KeyframeError,
+        event::{AnimationFinished, AnimationLooped, AnimationStarted, TweenFinished},
+        layer::KeyframeAnimationLayer,
+        lerp::{Animatable, Lerp},
+        marker::{KeyframeMarker, KeyframeMarkerEvent},
+        property::{
+            property_animation_player, PropertyAnimationClip, PropertyAnimationPlayer,
+            PropertyCurve, PropertyInterpolators,
+        },
+        queue::KeyframeQueuedClip,
+        repeat::{FinishMode, RepeatMode},
+        spline::{KeyframeHandles, KeyframeInterpolation, KeyframeTangents, KochanekBartels},
+        state::{
+            animation_state_machine, AnimationCondition, AnimationParameter, AnimationState,
+            AnimationStateMachine, AnimationStateMachineSystem, AnimationStateTransition,
+        },
+        tween::{tween_player, Tween},
+        *,
+    };
+
+    #[cfg(feature = "serialize")]
+    pub use crate::loader::{parse_clip, KeyframeClipLoader, KeyframeRepr};
+
+    #[cfg(feature = "ui")]
+    pub use crate::channels::{StyleChannels, TextChannels};
 }
 
-use std::ops::Deref;
+use std::{borrow::Cow, collections::VecDeque};
 
-use bevy::{hierarchy::HierarchySystem, prelude::*, transform::TransformSystem, utils::HashMap};
+use bevy::{
+    asset::Asset,
+    hierarchy::HierarchySystem,
+    prelude::*,
+    reflect::TypeUuid,
+    transform::TransformSystem,
+    utils::{HashMap, HashSet, Uuid},
+};
 
-use crate::lerp::Lerp;
+#[cfg(feature = "serialize")]
+use crate::loader::KeyframeClipLoader;
+use crate::{
+    blend::keyframe_blend_tree_player,
+    builder::{KeyframeClipBuilder, KeyframeCurveBuilder},
+    cursor::{find_segment_from, KeyframeCursors},
+    ease::Ease,
+    error::KeyframeError,
+    event::{AnimationFinished, AnimationLooped, AnimationStarted, TweenFinished},
+    layer::KeyframeAnimationLayer,
+    lerp::Lerp,
+    marker::{crossed_markers, KeyframeMarker, KeyframeMarkerEvent},
+    property::{property_animation_player, PropertyInterpolators},
+    queue::KeyframeQueuedClip,
+    repeat::{clip_elapsed, crossed_loops, is_finished, FinishMode, RepeatMode},
+    spline::KeyframeInterpolation,
+    state::{animation_state_machine, AnimationStateMachineSystem},
+    target::{HierarchyChanges, KeyframeTargetCache},
+    tween::tween_player,
+};
 
 /// Wrapper around a type that can be eased.
+#[derive(Clone, Debug, Default)]
 pub struct Keyframe<T>(pub T);
 
+impl<T> From<T> for Keyframe<T> {
+    fn from(value: T) -> Self {
+        Keyframe(value)
+    }
+}
+
 impl<T> Lerp<T> for Keyframe<T>
 where
     T: Lerp<T>,
 {
-    fn lerp(&self, other: &Self, scalar: f32, target: &T, options: &Option<Vec<String>>) -> Self {
-        Keyframe(self.0.lerp(&other.0, scalar, target, options))
+    type Channels = T::Channels;
+
+    fn lerp(&self, other: &Self, scalar: f32, target: &T, channels: &T::Channels) -> Self {
+        Keyframe(self.0.lerp(&other.0, scalar, target, channels))
+    }
+
+    fn add_to(&self, base: &T, channels: &T::Channels) -> Self {
+        Keyframe(self.0.add_to(base, channels))
     }
 }
 
+/// Channels of `T` that a [`KeyframeVariableCurve<T>`] can animate.
+pub type KeyframeChannels<T> = <Keyframe<T> as Lerp<T>>::Channels;
+
 /// Describes how an attribute of a [`Entity`] should be animated.
 ///
-/// `keyframe_timestamps` and `keyframes` should have the same length.
-pub struct KeyframeVariableCurve<T> {
+/// `keyframe_timestamps` and `keyframes` should have the same length, with
+/// increasing timestamps, as checked by
+/// [`validate`](KeyframeVariableCurve::validate). The parts of an invalid
+/// curve that can't be sampled are skipped.
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(bound(
+        serialize = "Keyframe<T>: serde::Serialize, KeyframeChannels<T>: serde::Serialize",
+        deserialize = "Keyframe<T>: serde::Deserialize<'de>, \
+                       KeyframeChannels<T>: serde::Deserialize<'de>"
+    ))
+)]
+pub struct KeyframeVariableCurve<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
     /// Timestamp for each of the keyframes.
     pub keyframe_timestamps: Vec<f32>,
     /// List of the keyframes.
     pub keyframes: Vec<Keyframe<T>>,
 
-    pub options: Option<Vec<String>>,
+    /// Attributes of `T` animated by the curve, all of them by default.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub channels: KeyframeChannels<T>,
+
+    /// Easing applied to every segment of the curve.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub ease: Ease,
+    /// Easing of each segment, overriding `ease`. The segment `i` goes from
+    /// the keyframe `i` to the keyframe `i + 1`.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub segment_eases: Option<Vec<Ease>>,
+    /// How values are interpolated between two keyframes.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub interpolation: KeyframeInterpolation<T>,
+    /// How each keyframe is applied, all of them are absolute by default.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub keyframe_modes: Option<Vec<KeyframeMode>>,
+}
+
+/// How the value of a keyframe is applied, with the base value of the
+/// component captured when the player started its clip.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyframeMode {
+    /// The keyframe is the value.
+    #[default]
+    Absolute,
+    /// The keyframe is an offset from the base value, see [`Lerp::add_to`],
+    /// so that a clip can be reused on objects at different positions.
+    Relative,
+    /// The keyframe is the base value, ignoring the keyframe itself, e.g. to
+    /// move from wherever the object is to an absolute keyframe.
+    Current,
+}
+
+impl<T> Default for KeyframeVariableCurve<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    fn default() -> Self {
+        Self {
+            keyframe_timestamps: Vec::new(),
+            keyframes: Vec::new(),
+            channels: Default::default(),
+            ease: Ease::Linear,
+            segment_eases: None,
+            interpolation: KeyframeInterpolation::Linear,
+            keyframe_modes: None,
+        }
+    }
+}
+
+impl<T> KeyframeVariableCurve<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    /// Create a builder adding the keyframes of a curve one by one
+    pub fn builder() -> KeyframeCurveBuilder<T> {
+        KeyframeCurveBuilder::default()
+    }
+
+    /// Create a curve animating every attribute of `T` linearly, after
+    /// validating its keyframes.
+    pub fn try_new(
+        keyframe_timestamps: Vec<f32>,
+        keyframes: Vec<Keyframe<T>>,
+    ) -> Result<Self, KeyframeError> {
+        let curve = Self {
+            keyframe_timestamps,
+            keyframes,
+            ..Default::default()
+        };
+        curve.validate()?;
+        Ok(curve)
+    }
+
+    /// Check that there's one keyframe per timestamp, and that the
+    /// timestamps are finite and strictly increasing.
+    pub fn validate(&self) -> Result<(), KeyframeError> {
+        if self.keyframe_timestamps.len() != self.keyframes.len() {
+            return Err(KeyframeError::LengthMismatch {
+                timestamps: self.keyframe_timestamps.len(),
+                keyframes: self.keyframes.len(),
+            });
+        }
+        if let KeyframeInterpolation::CubicBezier(handles) = &self.interpolation {
+            if handles.len() != self.keyframes.len() {
+                return Err(KeyframeError::HandlesMismatch {
+                    keyframes: self.keyframes.len(),
+                    handles: handles.len(),
+                });
+            }
+        }
+        if let Some(keyframe_modes) = &self.keyframe_modes {
+            if keyframe_modes.len() != self.keyframes.len() {
+                return Err(KeyframeError::ModesMismatch {
+                    keyframes: self.keyframes.len(),
+                    modes: keyframe_modes.len(),
+                });
+            }
+        }
+        for (index, timestamp) in self.keyframe_timestamps.iter().copied().enumerate() {
+            if !timestamp.is_finite() {
+                return Err(KeyframeError::InvalidTimestamp { index, timestamp });
+            }
+            let previous = match index.checked_sub(1) {
+                Some(previous) => self.keyframe_timestamps[previous],
+                None => continue,
+            };
+            if timestamp == previous {
+                return Err(KeyframeError::DuplicateTimestamp { index, timestamp });
+            } else if timestamp < previous {
+                return Err(KeyframeError::UnsortedTimestamp { index, timestamp });
+            }
+        }
+        Ok(())
+    }
+
+    /// Easing of the segment starting at the keyframe `segment`.
+    pub fn segment_ease(&self, segment: usize) -> Ease {
+        self.segment_eases
+            .as_ref()
+            .and_then(|eases| eases.get(segment))
+            .copied()
+            .unwrap_or(self.ease)
+    }
+
+    /// How the keyframe `keyframe` is applied.
+    pub fn keyframe_mode(&self, keyframe: usize) -> KeyframeMode {
+        self.keyframe_modes
+            .as_ref()
+            .and_then(|modes| modes.get(keyframe))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl<T> KeyframeVariableCurve<T>
+where
+    T: Clone,
+    Keyframe<T>: Lerp<T>,
+{
+    /// Sample the curve at `elapsed`, applying the animated attributes over
+    /// `target`. Returns `None` when the curve isn't started yet or is
+    /// finished. Relative and current keyframes are applied to `target`.
+    pub fn sample(&self, elapsed: f32, target: &T) -> Option<Keyframe<T>> {
+        // Starting out of the curve searches the whole curve
+        let mut segment = usize::MAX;
+        self.sample_from(elapsed, target, &mut segment)
+    }
+
+    /// Sample the curve at `elapsed` like [`sample`](Self::sample), looking
+    /// for the keyframes around `elapsed` from the segment `segment` before
+    /// searching the whole curve. `segment` is updated to the segment found,
+    /// so that curves played forward or backward aren't searched every frame.
+    pub fn sample_from(
+        &self,
+        elapsed: f32,
+        target: &T,
+        segment: &mut usize,
+    ) -> Option<Keyframe<T>> {
+        self.sample_relative(elapsed, target, target, segment)
+    }
+
+    /// Sample the curve at `elapsed` like [`sample_from`](Self::sample_from),
+    /// applying the relative and current keyframes to `base`. The Bézier
+    /// handles of the curve stay absolute.
+    pub fn sample_relative(
+        &self,
+        elapsed: f32,
+        target: &T,
+        base: &T,
+        segment: &mut usize,
+    ) -> Option<Keyframe<T>> {
+        // Some curves have only one keyframe used to set a keyframe
+        if self.keyframe_timestamps.len() == 1 {
+            let keyframe = self.resolve_keyframe(0, base)?;
+            return Some(keyframe.lerp(&keyframe, 1.0, target, &self.channels));
+        }
+
+        let (step_start, progress) =
+            find_segment_from(&self.keyframe_timestamps, elapsed, *segment)?;
+        *segment = step_start;
+        let lerp = self.segment_ease(step_start).apply(progress);
+
+        Some(self.interpolation.interpolate(
+            step_start,
+            &*self.resolve_keyframe(step_start, base)?,
+            &*self.resolve_keyframe(step_start + 1, base)?,
+            lerp,
+            target,
+            &self.channels,
+        ))
+    }
+
+    /// Value of the keyframe `index` given its mode, on top of `base`.
+    fn resolve_keyframe(&self, index: usize, base: &T) -> Option<Cow<'_, Keyframe<T>>> {
+        let keyframe = self.keyframes.get(index)?;
+        Some(match self.keyframe_mode(index) {
+            KeyframeMode::Absolute => Cow::Borrowed(keyframe),
+            KeyframeMode::Relative => Cow::Owned(keyframe.add_to(base, &self.channels)),
+            KeyframeMode::Current => Cow::Owned(Keyframe(base.clone())),
+        })
+    }
+}
+
+/// Find the segment of a curve containing `elapsed`, and the progress in it
+/// between `0.0` and `1.0`. Returns `None` when the curve isn't started yet or
+/// is finished.
+pub(crate) fn find_segment(keyframe_timestamps: &[f32], elapsed: f32) -> Option<(usize, f32)> {
+    let last = keyframe_timestamps.len().checked_sub(2)?;
+    // Find the current keyframe, the last one not after `elapsed`
+    let step_start = match keyframe_timestamps.partition_point(|timestamp| *timestamp <= elapsed) {
+        0 => return None, // this curve isn't started yet
+        // Clamp to the last segment so the final keyframe can be reached
+        n if n == last + 2 && elapsed == keyframe_timestamps[last + 1] => last,
+        n if n == last + 2 => return None, // this curve is finished
+        n => n - 1,
+    };
+    Some((
+        step_start,
+        segment_progress(keyframe_timestamps, step_start, elapsed),
+    ))
+}
+
+/// Progress in the segment starting at the keyframe `segment` at `elapsed`.
+pub(crate) fn segment_progress(keyframe_timestamps: &[f32], segment: usize, elapsed: f32) -> f32 {
+    let ts_start = keyframe_timestamps[segment];
+    let ts_end = keyframe_timestamps[segment + 1];
+    if ts_end > ts_start {
+        (elapsed - ts_start) / (ts_end - ts_start)
+    } else {
+        // Invalid curve with a segment of no duration
+        1.0
+    }
 }
 
 /// Path to an entity, with [`Name`]s. Each entity in a path must have a name.
@@ -42,13 +388,76 @@ pub struct KeyframeEntityPath {
     pub parts: Vec<Name>,
 }
 
-#[derive(Default, Component)]
-pub struct KeyframeAnimationClip<T> {
+/// Parse a path from the names of its parts separated by slashes, e.g.
+/// `"root/arm/hand"`.
+impl From<&str> for KeyframeEntityPath {
+    fn from(path: &str) -> Self {
+        KeyframeEntityPath {
+            parts: path
+                .split('/')
+                .filter(|part| !part.is_empty())
+                .map(|part| Name::new(part.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl KeyframeEntityPath {
+    /// Whether the path is `prefix` or a path to one of its descendants.
+    pub fn starts_with(&self, prefix: &KeyframeEntityPath) -> bool {
+        self.parts.starts_with(&prefix.parts)
+    }
+}
+
+/// An animation of a component on one or many entities, stored as an asset
+/// so that it can be shared by all the players.
+#[derive(Component)]
+pub struct KeyframeAnimationClip<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
     curves: HashMap<KeyframeEntityPath, Vec<KeyframeVariableCurve<T>>>,
+    markers: Vec<KeyframeMarker>,
+    blend_mode: KeyframeBlendMode,
     duration: f32,
 }
 
-impl<T> KeyframeAnimationClip<T> {
+/// How the values of a clip are applied to the animated components.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyframeBlendMode {
+    /// The keyframes replace the values of the components.
+    #[default]
+    Override,
+    /// The keyframes are deltas added on top of the values of the components,
+    /// see [`Lerp::add_to`]. In a layer, they are added to the layers below,
+    /// otherwise to the values captured when the clip started.
+    Additive,
+}
+
+impl<T> Default for KeyframeAnimationClip<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    fn default() -> Self {
+        Self {
+            curves: HashMap::default(),
+            markers: Vec::new(),
+            blend_mode: KeyframeBlendMode::Override,
+            duration: 0.0,
+        }
+    }
+}
+
+impl<T> KeyframeAnimationClip<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    /// Create a builder adding the curves of a clip one by one
+    pub fn builder() -> KeyframeClipBuilder<T> {
+        KeyframeClipBuilder::default()
+    }
+
     #[inline]
     /// Hashmap of the [`VariableCurve`]s per [`EntityPath`].
     pub fn curves(&self) -> &HashMap<KeyframeEntityPath, Vec<KeyframeVariableCurve<T>>> {
@@ -61,6 +470,18 @@ impl<T> KeyframeAnimationClip<T> {
         self.duration
     }
 
+    /// Add a [`KeyframeVariableCurve`] to a [`KeyframeEntityPath`], after
+    /// [validating](KeyframeVariableCurve::validate) it.
+    pub fn try_add_curve_to_path(
+        &mut self,
+        path: KeyframeEntityPath,
+        curve: KeyframeVariableCurve<T>,
+    ) -> Result<(), KeyframeError> {
+        curve.validate()?;
+        self.add_curve_to_path(path, curve);
+        Ok(())
+    }
+
     /// Add a [`VariableCurve`] to an [`EntityPath`].
     pub fn add_curve_to_path(&mut self, path: KeyframeEntityPath, curve: KeyframeVariableCurve<T>) {
         // Update the duration of the animation by this curve duration if it's longer
@@ -69,51 +490,321 @@ impl<T> KeyframeAnimationClip<T> {
             .max(*curve.keyframe_timestamps.last().unwrap_or(&0.0));
         self.curves.entry(path).or_default().push(curve);
     }
+
+    /// Markers of the clip, sending a [`KeyframeMarkerEvent`] when crossed.
+    #[inline]
+    pub fn markers(&self) -> &[KeyframeMarker] {
+        &self.markers
+    }
+
+    /// Add a [`KeyframeMarker`] to the clip.
+    pub fn add_marker(&mut self, marker: KeyframeMarker) {
+        self.duration = self.duration.max(marker.timestamp);
+        self.markers.push(marker);
+    }
+
+    /// How the values of the clip are applied
+    #[inline]
+    pub fn blend_mode(&self) -> KeyframeBlendMode {
+        self.blend_mode
+    }
+
+    /// Set how the values of the clip are applied
+    pub fn set_blend_mode(&mut self, blend_mode: KeyframeBlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    /// Sample the curves of `path` at `elapsed`, each curve applied on top of
+    /// the previous ones. Returns `None` if none of them applies. The
+    /// `cursors` remember where the curves were sampled for the next frame.
+    pub(crate) fn sample(
+        &self,
+        path: &KeyframeEntityPath,
+        elapsed: f32,
+        target: &T,
+        base: Option<&T>,
+        cursors: Option<&mut KeyframeCursors>,
+    ) -> Option<Keyframe<T>>
+    where
+        T: Clone,
+    {
+        let curves = self.curves.get(path)?;
+        let mut segments = cursors.map(|cursors| cursors.segments(path, curves.len()));
+        let mut keyframe: Option<Keyframe<T>> = None;
+        for (index, curve) in curves.iter().enumerate() {
+            let target = keyframe.as_ref().map_or(target, |keyframe| &keyframe.0);
+            let mut segment = usize::MAX;
+            let segment = match &mut segments {
+                Some(segments) => &mut segments[index],
+                None => &mut segment,
+            };
+            let sampled = curve.sample_relative(elapsed, target, base.unwrap_or(target), segment);
+            if let Some(sampled) = sampled {
+                keyframe = Some(match self.blend_mode {
+                    KeyframeBlendMode::Override => sampled,
+                    KeyframeBlendMode::Additive => sampled.add_to(target, &curve.channels),
+                });
+            }
+        }
+        keyframe
+    }
+
+    /// Base value of the relative and current keyframes of `path`, or of the
+    /// deltas of an additive clip, captured from `object` the first time it's
+    /// sampled. `None` if the curves of the path are absolute.
+    pub(crate) fn capture_base<'a>(
+        &self,
+        path: &KeyframeEntityPath,
+        bases: &'a mut HashMap<Entity, T>,
+        target: Entity,
+        object: &T,
+    ) -> Option<&'a T>
+    where
+        T: Clone,
+    {
+        self.curves
+            .get(path)
+            .into_iter()
+            .flatten()
+            .any(|curve| {
+                curve.keyframe_modes.is_some() || self.blend_mode == KeyframeBlendMode::Additive
+            })
+            .then(|| &*bases.entry(target).or_insert_with(|| object.clone()))
+    }
+
+    /// Value the clip is sampled on top of: the captured `base` for additive
+    /// clips, so that the deltas don't accumulate from frame to frame, and
+    /// `object` otherwise.
+    pub(crate) fn sampling_target<'a>(&self, base: Option<&'a T>, object: &'a T) -> &'a T {
+        match (self.blend_mode, base) {
+            (KeyframeBlendMode::Additive, Some(base)) => base,
+            _ => object,
+        }
+    }
+}
+
+/// Uuid of the [`KeyframeAnimationClip`] asset of a component. Each animated
+/// component needs its own, as assets are told apart by their uuid.
+pub trait KeyframeClipUuid {
+    const CLIP_UUID: Uuid;
+}
+
+impl<T> TypeUuid for KeyframeAnimationClip<T>
+where
+    T: KeyframeClipUuid,
+    Keyframe<T>: Lerp<T>,
+{
+    const TYPE_UUID: Uuid = T::CLIP_UUID;
 }
 
 #[derive(Component)]
-pub struct KeyframeAnimationPlayer<T> {
+pub struct KeyframeAnimationPlayer<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
     paused: bool,
-    repeat: bool,
+    repeat_mode: RepeatMode,
+    finish_mode: FinishMode,
+    speed: f32,
+    elapsed: f32,
+    started: bool,
+    loop_count: u32,
+    finished: bool,
+    animation_clip: Handle<KeyframeAnimationClip<T>>,
+    transition: Option<KeyframeTransition<T>>,
+    layers: Vec<KeyframeAnimationLayer<T>>,
+    queue: VecDeque<KeyframeQueuedClip<T>>,
+    target_cache: KeyframeTargetCache,
+    cursors: KeyframeCursors,
+    /// Values of the targets when the clip started, for relative keyframes
+    bases: HashMap<Entity, T>,
+}
+
+/// Clip faded out by a transition, still played until the transition ends.
+struct KeyframeTransition<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    animation_clip: Handle<KeyframeAnimationClip<T>>,
+    repeat_mode: RepeatMode,
     speed: f32,
     elapsed: f32,
-    animation_clip: KeyframeAnimationClip<T>,
+    /// Time elapsed since the start of the transition
+    progress: f32,
+    duration: f32,
+    cursors: KeyframeCursors,
+    bases: HashMap<Entity, T>,
 }
 
-impl<T> KeyframeAnimationPlayer<T> {
-    pub fn new(animation_clip: KeyframeAnimationClip<T>) -> Self {
+impl<T> KeyframeAnimationPlayer<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    pub fn new(animation_clip: Handle<KeyframeAnimationClip<T>>) -> Self {
         Self {
             paused: false,
-            repeat: false,
+            repeat_mode: RepeatMode::Once,
+            finish_mode: FinishMode::Hold,
             speed: 1.0,
             elapsed: 0.0,
+            started: false,
+            loop_count: 0,
+            finished: false,
             animation_clip,
+            transition: None,
+            layers: Vec::new(),
+            queue: VecDeque::new(),
+            target_cache: KeyframeTargetCache::default(),
+            cursors: KeyframeCursors::default(),
+            bases: HashMap::default(),
         }
     }
 }
 
-impl<T> KeyframeAnimationPlayer<T> {
-    /// Start playing an animation, resetting state of the player
-    pub fn play(&mut self, handle: KeyframeAnimationClip<T>) -> &mut Self {
-        *self = Self {
-            animation_clip: handle,
-            paused: false,
-            repeat: false,
-            speed: 1.0,
-            elapsed: 0.0,
+impl<T> KeyframeAnimationPlayer<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    /// Start playing an animation, resetting state of the player and
+    /// clearing its queue. The relative keyframes of the animation are
+    /// applied to the values of the targets when it starts.
+    pub fn play(&mut self, handle: Handle<KeyframeAnimationClip<T>>) -> &mut Self {
+        let layers = std::mem::take(&mut self.layers);
+        let target_cache = std::mem::take(&mut self.target_cache);
+        *self = Self::new(handle);
+        self.layers = layers;
+        self.target_cache = target_cache;
+        self
+    }
+
+    /// Start playing an animation, crossfading from the current one over
+    /// `duration` seconds. The current animation keeps playing during the
+    /// transition, and its curves are blended with the new ones. An ongoing
+    /// transition is cut short, and the queue is cleared. A `duration` that
+    /// isn't positive plays the animation right away, like [`play`](Self::play).
+    pub fn play_with_transition(
+        &mut self,
+        handle: Handle<KeyframeAnimationClip<T>>,
+        duration: f32,
+    ) -> &mut Self {
+        if !(duration.is_finite() && duration > 0.0) {
+            return self.play(handle);
+        }
+        let transition = KeyframeTransition {
+            animation_clip: self.animation_clip.clone(),
+            repeat_mode: self.repeat_mode,
+            speed: self.speed,
+            elapsed: self.elapsed,
+            progress: 0.0,
+            duration,
+            cursors: std::mem::take(&mut self.cursors),
+            bases: std::mem::take(&mut self.bases),
         };
+        let layers = std::mem::take(&mut self.layers);
+        let target_cache = std::mem::take(&mut self.target_cache);
+        *self = Self::new(handle);
+        self.transition = Some(transition);
+        self.layers = layers;
+        self.target_cache = target_cache;
+        self
+    }
+
+    /// Queue an animation, played once the current one and the ones queued
+    /// before it finish
+    pub fn queue(&mut self, handle: Handle<KeyframeAnimationClip<T>>) -> &mut Self {
+        self.queue_clip(KeyframeQueuedClip::new(handle))
+    }
+
+    /// Queue an animation with its own repeat mode, speed and transition
+    pub fn queue_clip(&mut self, queued_clip: KeyframeQueuedClip<T>) -> &mut Self {
+        self.queue.push_back(queued_clip);
+        self
+    }
+
+    /// Animations queued after the current one, in order
+    pub fn queued(&self) -> impl Iterator<Item = &KeyframeQueuedClip<T>> {
+        self.queue.iter()
+    }
+
+    /// Remove the queued animations
+    pub fn clear_queue(&mut self) -> &mut Self {
+        self.queue.clear();
+        self
+    }
+
+    /// Start playing the next queued animation, keeping the rest of the queue
+    fn play_next(&mut self) {
+        let queued_clip = match self.queue.pop_front() {
+            Some(queued_clip) => queued_clip,
+            None => return,
+        };
+        let queue = std::mem::take(&mut self.queue);
+        if queued_clip.transition > 0.0 {
+            self.play_with_transition(queued_clip.animation_clip, queued_clip.transition);
+        } else {
+            self.play(queued_clip.animation_clip);
+        }
+        self.set_repeat_mode(queued_clip.repeat_mode)
+            .set_speed(queued_clip.speed);
+        self.queue = queue;
+    }
+
+    /// Handle of the played animation
+    pub fn animation_clip(&self) -> &Handle<KeyframeAnimationClip<T>> {
+        &self.animation_clip
+    }
+
+    /// Layers played on top of the animation, from the bottom to the top
+    pub fn layers(&self) -> &[KeyframeAnimationLayer<T>] {
+        &self.layers
+    }
+
+    /// Mutable access to the layers, from the bottom to the top
+    pub fn layers_mut(&mut self) -> &mut Vec<KeyframeAnimationLayer<T>> {
+        &mut self.layers
+    }
+
+    /// Add a layer on top of the others
+    pub fn add_layer(&mut self, layer: KeyframeAnimationLayer<T>) -> &mut Self {
+        self.layers.push(layer);
         self
     }
 
     /// Set the animation to repeat
     pub fn repeat(&mut self) -> &mut Self {
-        self.repeat = true;
+        self.repeat_mode = RepeatMode::Loop;
         self
     }
 
     /// Stop the animation from repeating
     pub fn stop_repeating(&mut self) -> &mut Self {
-        self.repeat = false;
+        self.repeat_mode = RepeatMode::Once;
+        self
+    }
+
+    /// How the animation repeats
+    pub fn repeat_mode(&self) -> RepeatMode {
+        self.repeat_mode
+    }
+
+    /// Set how the animation repeats
+    pub fn set_repeat_mode(&mut self, repeat_mode: RepeatMode) -> &mut Self {
+        self.repeat_mode = repeat_mode;
+        self
+    }
+
+    /// What the animation shows once finished
+    pub fn finish_mode(&self) -> FinishMode {
+        self.finish_mode
+    }
+
+    /// Set what the animation shows once finished
+    pub fn set_finish_mode(&mut self, finish_mode: FinishMode) -> &mut Self {
+        self.finish_mode = finish_mode;
         self
     }
 
@@ -148,7 +839,13 @@ impl<T> KeyframeAnimationPlayer<T> {
         self.elapsed
     }
 
-    /// Seek to a specific time in the animation
+    /// Is the animation done playing. A repeating animation never finishes
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Seek to a specific time in the animation. The markers skipped over
+    /// aren't sent.
     pub fn set_elapsed(&mut self, elapsed: f32) -> &mut Self {
         self.elapsed = elapsed;
         self
@@ -156,95 +853,248 @@ impl<T> KeyframeAnimationPlayer<T> {
 }
 
 /// System that will play all animations, using any entity with a
-/// [`AnimationPlayer`] and a [`AnimationClip`] as an animation root
-pub fn keyframe_animation_player<T: Component>(
+/// [`KeyframeAnimationPlayer`] as an animation root. Each curve is applied to
+/// the `T` of the entity its [`KeyframeEntityPath`] resolves to, so the root
+/// itself doesn't need to have a `T`.
+///
+/// The [`KeyframeAnimationLayer`]s of the players are blended on top of their
+/// clip, in order.
+///
+/// The entities of the paths are resolved once per player, and again after a
+/// `Children` or `Name` changes.
+///
+/// Once the clip of a player finishes, the next clip of its queue starts on
+/// the following frame.
+///
+/// A [`KeyframeMarkerEvent`] is sent for each marker crossed while playing the
+/// main clip, along with [`AnimationStarted`], [`AnimationLooped`] and
+/// [`AnimationFinished`] events.
+#[allow(clippy::too_many_arguments)]
+pub fn keyframe_animation_player<T>(
     time: Res<Time>,
-    mut query: Query<(Entity, &mut T)>,
-    mut animation_players: Query<&mut KeyframeAnimationPlayer<T>>,
+    animations: Res<Assets<KeyframeAnimationClip<T>>>,
+    mut animation_players: Query<(Entity, &mut KeyframeAnimationPlayer<T>)>,
+    mut marker_events: EventWriter<KeyframeMarkerEvent>,
+    mut started_events: EventWriter<AnimationStarted>,
+    mut looped_events: EventWriter<AnimationLooped>,
+    mut finished_events: EventWriter<AnimationFinished>,
+    mut targets: Query<&mut T>,
     names: Query<&Name>,
     children: Query<&Children>,
+    hierarchy_changes: HierarchyChanges,
 ) where
+    T: Component + Clone,
     Keyframe<T>: Lerp<T>,
-    T: Default,
+    KeyframeAnimationClip<T>: Asset,
 {
-    for (entity, mut object) in query.iter_mut() {
-        if let Ok(mut player) = animation_players.get_mut(entity) {
-            // Continue if paused unless the `AnimationPlayer` was changed
-            // This allow the animation to still be updated if the player.elapsed field was
-            // manually updated in pause
-            if player.paused && !player.is_changed() {
-                continue;
+    let hierarchy_changed = !hierarchy_changes.is_empty();
+    for (entity, mut player) in animation_players.iter_mut() {
+        if hierarchy_changed {
+            player.target_cache.clear();
+        }
+        if player.finished && !player.paused {
+            player.play_next();
+        }
+        let animation_clip = match animations.get(&player.animation_clip) {
+            Some(animation_clip) => animation_clip,
+            // The clip isn't loaded yet
+            None => continue,
+        };
+        // Continue if paused unless the `AnimationPlayer` was changed
+        // This allow the animation to still be updated if the player.elapsed field was
+        // manually updated in pause
+        if player.paused && !player.is_changed() {
+            continue;
+        }
+        let clip = player.animation_clip.id;
+        if !player.started {
+            player.started = true;
+            started_events.send(AnimationStarted { entity, clip });
+        }
+        if !player.paused {
+            if let Some(transition) = &mut player.transition {
+                transition.elapsed += time.delta_seconds() * transition.speed;
+                transition.progress += time.delta_seconds();
+            }
+            for layer in &mut player.layers {
+                layer.elapsed += time.delta_seconds() * layer.speed;
             }
-            if !player.paused {
-                player.elapsed += time.delta_seconds() * player.speed;
+            let previous_elapsed = player.elapsed;
+            player.elapsed += time.delta_seconds() * player.speed;
+            for marker in crossed_markers(
+                &animation_clip.markers,
+                previous_elapsed,
+                player.elapsed,
+                animation_clip.duration,
+                player.repeat_mode,
+            ) {
+                marker_events.send(KeyframeMarkerEvent {
+                    entity,
+                    clip,
+                    name: marker.name.clone(),
+                    payload: marker.payload.clone(),
+                });
             }
-            let mut elapsed = player.elapsed;
-            if player.repeat {
-                elapsed %= player.animation_clip.duration;
+            for _ in 0..crossed_loops(
+                previous_elapsed,
+                player.elapsed,
+                animation_clip.duration,
+                player.repeat_mode,
+            ) {
+                player.loop_count += 1;
+                looped_events.send(AnimationLooped {
+                    entity,
+                    clip,
+                    loop_count: player.loop_count,
+                });
             }
-            if elapsed < 0.0 {
-                elapsed += player.animation_clip.duration;
+        }
+        let finished = is_finished(
+            player.elapsed,
+            animation_clip.duration,
+            player.speed,
+            player.repeat_mode,
+        );
+        if finished != player.finished {
+            player.finished = finished;
+            if finished {
+                finished_events.send(AnimationFinished { entity, clip });
+            }
+        }
+        let elapsed = if finished && player.finish_mode == FinishMode::Reset {
+            0.0
+        } else {
+            clip_elapsed(player.elapsed, animation_clip.duration, player.repeat_mode)
+        };
+        // Outgoing clip of a transition, with its time and the weight of the
+        // incoming clip
+        let outgoing = player.transition.as_ref().and_then(|transition| {
+            let outgoing_clip = animations.get(&transition.animation_clip)?;
+            let outgoing_elapsed = clip_elapsed(
+                transition.elapsed,
+                outgoing_clip.duration,
+                transition.repeat_mode,
+            );
+            let weight = (transition.progress / transition.duration).min(1.0);
+            Some((outgoing_clip, outgoing_elapsed, weight))
+        });
+        if matches!(outgoing, Some((_, _, weight)) if weight >= 1.0) {
+            player.transition = None;
+        }
+
+        let mut target_cache = std::mem::take(&mut player.target_cache);
+        let mut cursors = std::mem::take(&mut player.cursors);
+        let mut outgoing_cursors = player
+            .transition
+            .as_mut()
+            .map(|transition| std::mem::take(&mut transition.cursors))
+            .unwrap_or_default();
+        let mut bases = std::mem::take(&mut player.bases);
+        let mut outgoing_bases = player
+            .transition
+            .as_mut()
+            .map(|transition| std::mem::take(&mut transition.bases))
+            .unwrap_or_default();
+
+        // Layers with their clip, time and weight
+        let layers: Vec<_> = player
+            .layers
+            .iter()
+            .filter_map(|layer| {
+                let layer_clip = animations.get(&layer.animation_clip)?;
+                let layer_elapsed =
+                    clip_elapsed(layer.elapsed, layer_clip.duration, layer.repeat_mode);
+                Some((layer, layer_clip, layer_elapsed))
+            })
+            .collect();
+
+        // Paths animated by any of the clips
+        let mut paths: Vec<_> = animation_clip.curves.keys().collect();
+        let mut seen: HashSet<_> = paths.iter().copied().collect();
+        let other_paths = outgoing
+            .iter()
+            .flat_map(|(outgoing_clip, _, _)| outgoing_clip.curves.keys())
+            .chain(
+                layers
+                    .iter()
+                    .flat_map(|(_, layer_clip, _)| layer_clip.curves.keys()),
+            );
+        for path in other_paths {
+            if seen.insert(path) {
+                paths.push(path);
             }
-            'entity: for (path, curves) in &player.animation_clip.curves {
-                // PERF: finding the target entity can be optimised
-                let mut current_entity = entity;
-                // Ignore the first name, it is the root node which we already have
-                for part in path.parts.iter().skip(1) {
-                    let mut found = false;
-                    if let Ok(children) = children.get(current_entity) {
-                        for child in children.deref() {
-                            if let Ok(name) = names.get(*child) {
-                                if name == part {
-                                    // Found a children with the right name, continue to the
-                                    // next part
-                                    current_entity = *child;
-                                    found = true;
-                                    break;
-                                }
-                            }
+        }
+        for path in paths {
+            let target = match target_cache.get(entity, path, &children, &names) {
+                Some(target) => target,
+                None => continue,
+            };
+            let mut object = match targets.get_mut(target) {
+                Ok(object) => object,
+                // The entity exists but doesn't have the animated component
+                Err(_) => continue,
+            };
+            let base = animation_clip.capture_base(path, &mut bases, target, &object);
+            let incoming = animation_clip.sample(
+                path,
+                elapsed,
+                animation_clip.sampling_target(base, &object),
+                base,
+                Some(&mut cursors),
+            );
+            let keyframe = match outgoing {
+                Some((outgoing_clip, outgoing_elapsed, weight)) => {
+                    let outgoing_base =
+                        outgoing_clip.capture_base(path, &mut outgoing_bases, target, &object);
+                    let outgoing = outgoing_clip.sample(
+                        path,
+                        outgoing_elapsed,
+                        outgoing_clip.sampling_target(outgoing_base, &object),
+                        outgoing_base,
+                        Some(&mut outgoing_cursors),
+                    );
+                    match (outgoing, incoming) {
+                        // Blend the clips with the default channels of `T`
+                        (Some(from), Some(to)) => {
+                            Some(from.lerp(&to, weight, &*object, &Default::default()))
                         }
+                        (outgoing, incoming) => incoming.or(outgoing),
                     }
-                    if !found {
-                        warn!("Entity not found for path {:?} on part {:?}", path, part);
-                        continue 'entity;
-                    }
-                }
-                for curve in curves {
-                    // Some curves have only one keyframe used to set a keyframe
-                    if curve.keyframe_timestamps.len() == 1 {
-                        *object = Keyframe(T::default())
-                            .lerp(&curve.keyframes[0], 0., &*object, &curve.options)
-                            .0;
-                        continue;
-                    }
-
-                    // Find the current keyframe
-                    // PERF: finding the current keyframe can be optimised
-                    let step_start = match curve
-                        .keyframe_timestamps
-                        .binary_search_by(|probe| probe.partial_cmp(&elapsed).unwrap())
-                    {
-                        Ok(i) => i,
-                        Err(0) => continue, // this curve isn't started yet
-                        Err(n) if n > curve.keyframe_timestamps.len() - 1 => continue, /* this curve is finished */
-                        Err(i) => i - 1,
-                    };
-                    let ts_start = curve.keyframe_timestamps[step_start];
-                    let ts_end = curve.keyframe_timestamps[step_start + 1];
-                    let lerp = (elapsed - ts_start) / (ts_end - ts_start);
-
-                    // Apply the keyframe
-                    *object = curve.keyframes[step_start]
-                        .lerp(
-                            &curve.keyframes[step_start + 1],
-                            lerp,
-                            &*object,
-                            &curve.options,
-                        )
-                        .0;
                 }
+                None => incoming,
+            };
+            let keyframe =
+                layers
+                    .iter()
+                    .fold(keyframe, |keyframe, (layer, layer_clip, layer_elapsed)| {
+                        if layer.is_masked(path) {
+                            return keyframe;
+                        }
+                        // Sample the layer on top of the layers below
+                        let below = keyframe.as_ref().map_or(&*object, |keyframe| &keyframe.0);
+                        let base = layer_clip.capture_base(path, &mut bases, target, &object);
+                        match layer_clip.sample(path, *layer_elapsed, below, base, None) {
+                            Some(layer_keyframe) => Some(Keyframe(below.clone()).lerp(
+                                &layer_keyframe,
+                                layer.weight,
+                                below,
+                                &Default::default(),
+                            )),
+                            None => keyframe,
+                        }
+                    });
+            if let Some(keyframe) = keyframe {
+                // Apply the keyframe
+                *object = keyframe.0;
             }
         }
+        player.target_cache = target_cache;
+        player.cursors = cursors;
+        player.bases = bases;
+        if let Some(transition) = &mut player.transition {
+            transition.cursors = outgoing_cursors;
+            transition.bases = outgoing_bases;
+        }
     }
 }
 
@@ -254,14 +1104,804 @@ pub struct KeyframeAnimationPlugin;
 
 impl Plugin for KeyframeAnimationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_to_stage(
-            CoreStage::PostUpdate,
-            keyframe_animation_player::<Transform>
-                .before(TransformSystem::TransformPropagate)
-                .after(HierarchySystem::ParentUpdate),
-        )
-        .add_system(keyframe_animation_player::<Sprite>)
-        .add_system(keyframe_animation_player::<Handle<Image>>)
-        .add_system(keyframe_animation_player::<TextureAtlasSprite>);
+        app.add_asset::<KeyframeAnimationClip<Transform>>()
+            .add_asset::<KeyframeAnimationClip<Sprite>>()
+            .add_asset::<KeyframeAnimationClip<Handle<Image>>>()
+            .add_asset::<KeyframeAnimationClip<TextureAtlasSprite>>()
+            .add_event::<KeyframeMarkerEvent>()
+            .add_event::<AnimationStarted>()
+            .add_event::<AnimationLooped>()
+            .add_event::<AnimationFinished>()
+            .add_event::<TweenFinished>()
+            .init_resource::<PropertyInterpolators>()
+            .add_system_to_stage(
+                CoreStage::Update,
+                property_animation_player.exclusive_system().at_end(),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                keyframe_animation_player::<Transform>
+                    .before(TransformSystem::TransformPropagate)
+                    .after(HierarchySystem::ParentUpdate),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                keyframe_blend_tree_player::<Transform>
+                    .before(TransformSystem::TransformPropagate)
+                    .after(HierarchySystem::ParentUpdate),
+            )
+            .add_system(keyframe_animation_player::<Sprite>.after(AnimationStateMachineSystem))
+            .add_system(
+                keyframe_animation_player::<Handle<Image>>.after(AnimationStateMachineSystem),
+            )
+            .add_system(
+                keyframe_animation_player::<TextureAtlasSprite>.after(AnimationStateMachineSystem),
+            )
+            .add_system(keyframe_blend_tree_player::<Sprite>)
+            .add_system(keyframe_blend_tree_player::<Handle<Image>>)
+            .add_system(keyframe_blend_tree_player::<TextureAtlasSprite>)
+            .add_system(tween_player::<Transform>)
+            .add_system(tween_player::<Sprite>)
+            .add_system(tween_player::<Handle<Image>>)
+            .add_system(tween_player::<TextureAtlasSprite>)
+            .add_system(animation_state_machine::<Transform>.label(AnimationStateMachineSystem))
+            .add_system(animation_state_machine::<Sprite>.label(AnimationStateMachineSystem))
+            .add_system(animation_state_machine::<Handle<Image>>.label(AnimationStateMachineSystem))
+            .add_system(
+                animation_state_machine::<TextureAtlasSprite>.label(AnimationStateMachineSystem),
+            );
+
+        #[cfg(feature = "serialize")]
+        app.add_asset_loader(KeyframeClipLoader::<Transform>::new(&[
+            "transform.kanim.ron",
+            "transform.kanim.json",
+        ]))
+        .add_asset_loader(KeyframeClipLoader::<Sprite>::new(&[
+            "sprite.kanim.ron",
+            "sprite.kanim.json",
+        ]))
+        .add_asset_loader(KeyframeClipLoader::<TextureAtlasSprite>::new(&[
+            "atlas.kanim.ron",
+            "atlas.kanim.json",
+        ]));
+
+        #[cfg(feature = "ui")]
+        app.add_asset::<KeyframeAnimationClip<Style>>()
+            .add_asset::<KeyframeAnimationClip<UiColor>>()
+            .add_asset::<KeyframeAnimationClip<Text>>()
+            .add_asset::<KeyframeAnimationClip<UiImage>>()
+            .add_system(keyframe_animation_player::<Style>.after(AnimationStateMachineSystem))
+            .add_system(keyframe_animation_player::<UiColor>.after(AnimationStateMachineSystem))
+            .add_system(keyframe_animation_player::<Text>.after(AnimationStateMachineSystem))
+            .add_system(keyframe_animation_player::<UiImage>.after(AnimationStateMachineSystem))
+            .add_system(keyframe_blend_tree_player::<Style>)
+            .add_system(keyframe_blend_tree_player::<UiColor>)
+            .add_system(keyframe_blend_tree_player::<Text>)
+            .add_system(keyframe_blend_tree_player::<UiImage>)
+            .add_system(tween_player::<Style>)
+            .add_system(tween_player::<UiColor>)
+            .add_system(tween_player::<Text>)
+            .add_system(tween_player::<UiImage>)
+            .add_system(animation_state_machine::<Style>.label(AnimationStateMachineSystem))
+            .add_system(animation_state_machine::<UiColor>.label(AnimationStateMachineSystem))
+            .add_system(animation_state_machine::<Text>.label(AnimationStateMachineSystem))
+            .add_system(animation_state_machine::<UiImage>.label(AnimationStateMachineSystem));
+
+        #[cfg(all(feature = "ui", feature = "serialize"))]
+        app.add_asset_loader(KeyframeClipLoader::<Style>::new(&[
+            "style.kanim.ron",
+            "style.kanim.json",
+        ]))
+        .add_asset_loader(KeyframeClipLoader::<UiColor>::new(&[
+            "ui_color.kanim.ron",
+            "ui_color.kanim.json",
+        ]))
+        .add_asset_loader(KeyframeClipLoader::<Text>::new(&[
+            "text.kanim.ron",
+            "text.kanim.json",
+        ]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::AssetPlugin,
+        core::CorePlugin,
+        ecs::{event::Events, schedule::SystemStage},
+    };
+
+    use super::*;
+    use crate::{channels::TransformChannels, spline::KeyframeTangents};
+
+    fn path(parts: &[&str]) -> KeyframeEntityPath {
+        KeyframeEntityPath {
+            parts: parts
+                .iter()
+                .map(|part| Name::new(part.to_string()))
+                .collect(),
+        }
+    }
+
+    fn translation_curve(from: Vec3, to: Vec3) -> KeyframeVariableCurve<Transform> {
+        KeyframeVariableCurve {
+            keyframe_timestamps: vec![0.0, 1.0],
+            keyframes: Keyframe::translation(vec![from, to]),
+            channels: TransformChannels::TRANSLATION.into(),
+            ..Default::default()
+        }
+    }
+
+    fn world() -> World {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<KeyframeAnimationClip<Transform>>()
+            .add_event::<KeyframeMarkerEvent>()
+            .add_event::<AnimationStarted>()
+            .add_event::<AnimationLooped>()
+            .add_event::<AnimationFinished>();
+        std::mem::take(&mut app.world)
+    }
+
+    fn add(
+        world: &mut World,
+        animation: KeyframeAnimationClip<Transform>,
+    ) -> Handle<KeyframeAnimationClip<Transform>> {
+        world
+            .resource_mut::<Assets<KeyframeAnimationClip<Transform>>>()
+            .add(animation)
+    }
+
+    fn run(world: &mut World) {
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(keyframe_animation_player::<Transform>);
+        stage.run(world);
+    }
+
+    #[test]
+    fn curves_apply_to_resolved_children() {
+        let mut world = world();
+
+        let mut animation = KeyframeAnimationClip::default();
+        animation.add_curve_to_path(path(&["root"]), translation_curve(Vec3::ZERO, Vec3::X));
+        animation.add_curve_to_path(
+            path(&["root", "arm"]),
+            translation_curve(Vec3::ZERO, Vec3::Y),
+        );
+        animation.add_curve_to_path(
+            path(&["root", "arm", "hand"]),
+            translation_curve(Vec3::ZERO, Vec3::Z),
+        );
+        let mut player = KeyframeAnimationPlayer::new(add(&mut world, animation));
+        player.set_elapsed(0.5);
+
+        let mut hand = None;
+        let mut arm = None;
+        let root = world
+            .spawn()
+            .insert_bundle((Name::new("root"), Transform::default(), player))
+            .with_children(|p| {
+                arm = Some(
+                    p.spawn()
+                        .insert_bundle((Name::new("arm"), Transform::default()))
+                        .with_children(|p| {
+                            hand = Some(
+                                p.spawn()
+                                    .insert_bundle((Name::new("hand"), Transform::default()))
+                                    .id(),
+                            );
+                        })
+                        .id(),
+                );
+            })
+            .id();
+
+        run(&mut world);
+
+        let translation = |entity| world.get::<Transform>(entity).unwrap().translation;
+        assert_eq!(translation(root), Vec3::X * 0.5);
+        assert_eq!(translation(arm.unwrap()), Vec3::Y * 0.5);
+        assert_eq!(translation(hand.unwrap()), Vec3::Z * 0.5);
+    }
+
+    #[test]
+    fn targets_are_resolved_again_after_renames() {
+        let mut world = world();
+
+        let mut animation = KeyframeAnimationClip::default();
+        animation.add_curve_to_path(
+            path(&["root", "arm"]),
+            translation_curve(Vec3::ZERO, Vec3::Y),
+        );
+        let mut player = KeyframeAnimationPlayer::new(add(&mut world, animation));
+        player.set_elapsed(0.5);
+
+        let mut child = None;
+        world
+            .spawn()
+            .insert_bundle((Name::new("root"), Transform::default(), player))
+            .with_children(|p| {
+                child = Some(
+                    p.spawn()
+                        .insert_bundle((Name::new("leg"), Transform::default()))
+                        .id(),
+                );
+            });
+        let child = child.unwrap();
+
+        // Keep the same system, so that its change detection is kept
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(keyframe_animation_player::<Transform>);
+        stage.run(&mut world);
+        assert_eq!(
+            world.get::<Transform>(child).unwrap().translation,
+            Vec3::ZERO
+        );
+
+        *world.get_mut::<Name>(child).unwrap() = Name::new("arm");
+        stage.run(&mut world);
+        assert_eq!(
+            world.get::<Transform>(child).unwrap().translation,
+            Vec3::Y * 0.5
+        );
+    }
+
+    #[test]
+    fn invalid_curves_are_rejected_without_panics() {
+        let keyframes = || Keyframe::translation(vec![Vec3::ZERO, Vec3::X, Vec3::Y]);
+        let error =
+            |timestamps: Vec<f32>| KeyframeVariableCurve::try_new(timestamps, keyframes()).err();
+        assert_eq!(
+            error(vec![0.0, 1.0]),
+            Some(KeyframeError::LengthMismatch {
+                timestamps: 2,
+                keyframes: 3
+            })
+        );
+        assert!(matches!(
+            error(vec![0.0, f32::NAN, 2.0]),
+            Some(KeyframeError::InvalidTimestamp { index: 1, .. })
+        ));
+        assert!(matches!(
+            error(vec![0.0, 1.0, 1.0]),
+            Some(KeyframeError::DuplicateTimestamp { index: 2, .. })
+        ));
+        assert!(matches!(
+            error(vec![0.0, 2.0, 1.0]),
+            Some(KeyframeError::UnsortedTimestamp { index: 2, .. })
+        ));
+        assert_eq!(error(vec![0.0, 1.0, 2.0]), None);
+
+        let mut clip = KeyframeAnimationClip::default();
+        assert!(clip
+            .try_add_curve_to_path(
+                path(&["root"]),
+                KeyframeVariableCurve {
+                    keyframe_timestamps: vec![1.0, 0.0],
+                    keyframes: keyframes(),
+                    ..Default::default()
+                }
+            )
+            .is_err());
+        assert!(clip.curves().is_empty());
+
+        // Invalid curves added anyway are sampled without panicking
+        for keyframe_timestamps in [
+            vec![0.0, 1.0, 2.0, 3.0],
+            vec![f32::NAN, 1.0, 2.0],
+            vec![1.0, 1.0, 1.0],
+            vec![2.0, 1.0, 0.0],
+        ] {
+            let curve = KeyframeVariableCurve {
+                keyframe_timestamps,
+                keyframes: keyframes(),
+                ..Default::default()
+            };
+            for elapsed in [f32::NAN, -1.0, 0.0, 0.5, 1.0, 2.5, 3.0, f32::INFINITY] {
+                curve.sample(elapsed, &Transform::default());
+            }
+        }
+    }
+
+    #[test]
+    fn players_share_clips() {
+        let mut world = world();
+
+        let mut animation = KeyframeAnimationClip::default();
+        animation.add_curve_to_path(path(&["coin"]), translation_curve(Vec3::ZERO, Vec3::X));
+        let animation = add(&mut world, animation);
+        let coins: Vec<_> = [0.25, 0.75]
+            .into_iter()
+            .map(|elapsed| {
+                let mut player = KeyframeAnimationPlayer::new(animation.clone());
+                player.set_elapsed(elapsed);
+                world
+                    .spawn()
+                    .insert_bundle((Name::new("coin"), Transform::default(), player))
+                    .id()
+            })
+            .collect();
+
+        run(&mut world);
+
+        let translation = |entity| world.get::<Transform>(entity).unwrap().translation;
+        assert_eq!(translation(coins[0]), Vec3::X * 0.25);
+        assert_eq!(translation(coins[1]), Vec3::X * 0.75);
+    }
+
+    #[test]
+    fn players_send_started_and_finished_events() {
+        let mut world = world();
+
+        let mut animation = KeyframeAnimationClip::default();
+        animation.add_curve_to_path(path(&["door"]), translation_curve(Vec3::ZERO, Vec3::X));
+        let player = KeyframeAnimationPlayer::new(add(&mut world, animation));
+        let door = world
+            .spawn()
+            .insert_bundle((Name::new("door"), Transform::default(), player))
+            .id();
+
+        let mut started = world.resource::<Events<AnimationStarted>>().get_reader();
+        let mut finished = world.resource::<Events<AnimationFinished>>().get_reader();
+        run(&mut world);
+        assert_eq!(started.iter(world.resource()).count(), 1);
+        assert_eq!(finished.iter(world.resource()).count(), 0);
+
+        // Seek to the end of the clip
+        world
+            .get_mut::<KeyframeAnimationPlayer<Transform>>(door)
+            .unwrap()
+            .set_elapsed(1.0);
+        run(&mut world);
+        run(&mut world);
+        let player = world
+            .get::<KeyframeAnimationPlayer<Transform>>(door)
+            .unwrap();
+        assert!(player.is_finished());
+        assert_eq!(started.iter(world.resource()).count(), 0);
+        let events: Vec<_> = finished.iter(world.resource()).collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entity, door);
+    }
+
+    #[test]
+    fn queued_clips_play_in_order() {
+        let mut world = world();
+
+        let clips = ["wind_up", "attack", "recover"].map(|_| {
+            let mut animation = KeyframeAnimationClip::default();
+            animation.add_curve_to_path(path(&["knight"]), translation_curve(Vec3::ZERO, Vec3::X));
+            add(&mut world, animation)
+        });
+        let mut player = KeyframeAnimationPlayer::new(clips[0].clone());
+        player
+            .queue(clips[1].clone())
+            .queue_clip(KeyframeQueuedClip::new(clips[2].clone()).with_speed(2.0));
+        let knight = world
+            .spawn()
+            .insert_bundle((Name::new("knight"), Transform::default(), player))
+            .id();
+
+        for clip in &clips[1..] {
+            // Seek to the end of the clip, the next one starts on the
+            // following frame
+            world
+                .get_mut::<KeyframeAnimationPlayer<Transform>>(knight)
+                .unwrap()
+                .set_elapsed(1.0);
+            run(&mut world);
+            run(&mut world);
+            let player = world
+                .get::<KeyframeAnimationPlayer<Transform>>(knight)
+                .unwrap();
+            assert_eq!(player.animation_clip(), clip);
+            assert!(!player.is_finished());
+        }
+        let player = world
+            .get::<KeyframeAnimationPlayer<Transform>>(knight)
+            .unwrap();
+        assert_eq!(player.speed(), 2.0);
+        assert_eq!(player.queued().count(), 0);
+    }
+
+    #[test]
+    fn finished_players_hold_or_reset() {
+        let mut world = world();
+
+        let mut animation = KeyframeAnimationClip::default();
+        animation.add_curve_to_path(path(&["door"]), translation_curve(Vec3::ZERO, Vec3::X));
+        let animation = add(&mut world, animation);
+        let doors: Vec<_> = [FinishMode::Hold, FinishMode::Reset]
+            .into_iter()
+            .map(|finish_mode| {
+                let mut player = KeyframeAnimationPlayer::new(animation.clone());
+                player
+                    .set_repeat_mode(RepeatMode::PingPongCount(1))
+                    .set_finish_mode(finish_mode)
+                    .set_elapsed(1.5);
+                world
+                    .spawn()
+                    .insert_bundle((Name::new("door"), Transform::default(), player))
+                    .id()
+            })
+            .collect();
+
+        // Halfway back
+        run(&mut world);
+        let translation =
+            |world: &World, entity| world.get::<Transform>(entity).unwrap().translation;
+        assert_eq!(translation(&world, doors[0]), Vec3::X * 0.5);
+        assert_eq!(translation(&world, doors[1]), Vec3::X * 0.5);
+
+        for &door in &doors {
+            world
+                .get_mut::<KeyframeAnimationPlayer<Transform>>(door)
+                .unwrap()
+                .set_repeat_mode(RepeatMode::Once)
+                .set_elapsed(3.0);
+        }
+        run(&mut world);
+        assert_eq!(translation(&world, doors[0]), Vec3::X);
+        assert_eq!(translation(&world, doors[1]), Vec3::ZERO);
+    }
+
+    #[test]
+    fn transitions_blend_clips() {
+        let mut world = world();
+
+        let mut idle = KeyframeAnimationClip::default();
+        idle.add_curve_to_path(path(&["hero"]), translation_curve(Vec3::ZERO, Vec3::ZERO));
+        let mut walk = KeyframeAnimationClip::default();
+        walk.add_curve_to_path(path(&["hero"]), translation_curve(Vec3::X, Vec3::X));
+        let idle = add(&mut world, idle);
+        let walk = add(&mut world, walk);
+
+        let mut player = KeyframeAnimationPlayer::new(idle);
+        player.play_with_transition(walk, 0.5);
+        let hero = world
+            .spawn()
+            .insert_bundle((Name::new("hero"), Transform::default(), player))
+            .id();
+
+        // The transition starts from the outgoing clip
+        run(&mut world);
+        let translation = |world: &World| world.get::<Transform>(hero).unwrap().translation;
+        assert_eq!(translation(&world), Vec3::ZERO);
+
+        let mut player = world
+            .get_mut::<KeyframeAnimationPlayer<Transform>>(hero)
+            .unwrap();
+        player.transition.as_mut().unwrap().progress = 0.25;
+        run(&mut world);
+        assert_eq!(translation(&world), Vec3::X * 0.5);
+
+        let mut player = world
+            .get_mut::<KeyframeAnimationPlayer<Transform>>(hero)
+            .unwrap();
+        player.transition.as_mut().unwrap().progress = 0.5;
+        run(&mut world);
+        assert_eq!(translation(&world), Vec3::X);
+        let player = world
+            .get::<KeyframeAnimationPlayer<Transform>>(hero)
+            .unwrap();
+        assert!(player.transition.is_none());
+    }
+
+    #[test]
+    fn transitions_without_duration_play_right_away() {
+        let mut world = world();
+
+        let mut idle = KeyframeAnimationClip::default();
+        idle.add_curve_to_path(path(&["hero"]), translation_curve(Vec3::ZERO, Vec3::ZERO));
+        let mut walk = KeyframeAnimationClip::default();
+        walk.add_curve_to_path(path(&["hero"]), translation_curve(Vec3::X, Vec3::X));
+        let idle = add(&mut world, idle);
+        let walk = add(&mut world, walk);
+
+        for duration in [0.0, -0.5, f32::NAN] {
+            let mut player = KeyframeAnimationPlayer::new(idle.clone());
+            player.play_with_transition(walk.clone(), duration);
+            assert!(player.transition.is_none());
+            let hero = world
+                .spawn()
+                .insert_bundle((Name::new("hero"), Transform::default(), player))
+                .id();
+
+            run(&mut world);
+            assert_eq!(world.get::<Transform>(hero).unwrap().translation, Vec3::X);
+            world.despawn(hero);
+        }
+    }
+
+    #[test]
+    fn layers_are_weighted_and_masked() {
+        let mut world = world();
+
+        let mut base = KeyframeAnimationClip::default();
+        base.add_curve_to_path(path(&["hero"]), translation_curve(Vec3::Y, Vec3::Y));
+        let mut wave = KeyframeAnimationClip::default();
+        wave.add_curve_to_path(path(&["hero"]), translation_curve(Vec3::X, Vec3::X));
+        wave.add_curve_to_path(path(&["hero", "arm"]), translation_curve(Vec3::X, Vec3::X));
+        let base = add(&mut world, base);
+        let wave = add(&mut world, wave);
+
+        let mut player = KeyframeAnimationPlayer::new(base);
+        let mut layer = KeyframeAnimationLayer::new(wave);
+        layer.weight = 0.5;
+        layer.mask = vec![path(&["hero", "arm"])];
+        player.add_layer(layer);
+
+        let mut arm = None;
+        let hero = world
+            .spawn()
+            .insert_bundle((Name::new("hero"), Transform::default(), player))
+            .with_children(|p| {
+                arm = Some(
+                    p.spawn()
+                        .insert_bundle((Name::new("arm"), Transform::default()))
+                        .id(),
+                );
+            })
+            .id();
+
+        run(&mut world);
+
+        let translation = |entity| world.get::<Transform>(entity).unwrap().translation;
+        assert_eq!(translation(hero), Vec3::Y);
+        assert_eq!(translation(arm.unwrap()), Vec3::X * 0.5);
+    }
+
+    #[test]
+    fn additive_layers_add_deltas() {
+        let mut world = world();
+
+        let mut base = KeyframeAnimationClip::default();
+        base.add_curve_to_path(
+            path(&["hero"]),
+            KeyframeVariableCurve {
+                keyframe_timestamps: vec![0.0],
+                keyframes: vec![Keyframe(Transform {
+                    translation: Vec3::Y,
+                    rotation: Quat::from_rotation_y(0.5),
+                    scale: Vec3::splat(2.0),
+                })],
+                ..Default::default()
+            },
+        );
+        let mut breathing = KeyframeAnimationClip::default();
+        breathing.set_blend_mode(KeyframeBlendMode::Additive);
+        breathing.add_curve_to_path(
+            path(&["hero"]),
+            KeyframeVariableCurve {
+                keyframe_timestamps: vec![0.0],
+                keyframes: vec![Keyframe(Transform {
+                    translation: Vec3::X,
+                    rotation: Quat::from_rotation_y(0.25),
+                    scale: Vec3::splat(1.5),
+                })],
+                ..Default::default()
+            },
+        );
+        let base = add(&mut world, base);
+        let breathing = add(&mut world, breathing);
+
+        let mut player = KeyframeAnimationPlayer::new(base);
+        player.add_layer(KeyframeAnimationLayer::new(breathing));
+        let hero = world
+            .spawn()
+            .insert_bundle((Name::new("hero"), Transform::default(), player))
+            .id();
+
+        // Running twice doesn't accumulate the deltas, as the base clip sets
+        // the transform every frame
+        run(&mut world);
+        run(&mut world);
+
+        let transform = world.get::<Transform>(hero).unwrap();
+        assert_eq!(transform.translation, Vec3::X + Vec3::Y);
+        assert!(
+            transform
+                .rotation
+                .angle_between(Quat::from_rotation_y(0.75))
+                < 1e-4
+        );
+        assert_eq!(transform.scale, Vec3::splat(3.0));
+    }
+
+    #[test]
+    fn additive_clips_add_deltas_to_the_starting_values() {
+        let mut world = world();
+
+        let mut nudge = KeyframeAnimationClip::default();
+        nudge.set_blend_mode(KeyframeBlendMode::Additive);
+        nudge.add_curve_to_path(
+            path(&["hero"]),
+            KeyframeVariableCurve {
+                keyframe_timestamps: vec![0.0],
+                keyframes: vec![Keyframe(Transform::from_translation(Vec3::X))],
+                channels: TransformChannels::TRANSLATION.into(),
+                ..Default::default()
+            },
+        );
+        let nudge = add(&mut world, nudge);
+        let hero = world
+            .spawn()
+            .insert_bundle((
+                Name::new("hero"),
+                Transform::from_xyz(0.0, 1.0, 0.0),
+                KeyframeAnimationPlayer::new(nudge),
+            ))
+            .id();
+
+        // Nothing else sets the transform, the delta is still added once
+        for _ in 0..3 {
+            run(&mut world);
+            assert_eq!(
+                world.get::<Transform>(hero).unwrap().translation,
+                Vec3::X + Vec3::Y
+            );
+        }
+    }
+
+    #[test]
+    fn relative_keyframes_apply_to_the_starting_values() {
+        let mut world = world();
+
+        let bob = KeyframeAnimationClip::builder()
+            .path("bobber")
+            .channel(TransformChannels::TRANSLATION)
+            .current_key(0.0)
+            .relative_key(1.0, Transform::from_xyz(0.0, 2.0, 0.0))
+            .build()
+            .unwrap();
+        let bob = add(&mut world, bob);
+
+        let bobbers: Vec<_> = [Vec3::ZERO, Vec3::new(5.0, 1.0, 0.0)]
+            .into_iter()
+            .map(|translation| {
+                let mut player = KeyframeAnimationPlayer::new(bob.clone());
+                player.set_elapsed(0.5);
+                world
+                    .spawn()
+                    .insert_bundle((
+                        Name::new("bobber"),
+                        Transform::from_translation(translation),
+                        player,
+                    ))
+                    .id()
+            })
+            .collect();
+
+        // Sampled twice, the offsets don't accumulate as the bases are kept
+        run(&mut world);
+        run(&mut world);
+
+        let translation = |entity| world.get::<Transform>(entity).unwrap().translation;
+        assert_eq!(translation(bobbers[0]), Vec3::Y);
+        assert_eq!(translation(bobbers[1]), Vec3::new(5.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn segments_are_eased() {
+        let mut world = world();
+
+        let mut animation = KeyframeAnimationClip::default();
+        animation.add_curve_to_path(
+            path(&["root"]),
+            KeyframeVariableCurve {
+                keyframe_timestamps: vec![0.0, 1.0, 2.0],
+                keyframes: Keyframe::translation(vec![Vec3::ZERO, Vec3::X, Vec3::ZERO]),
+                ease: Ease::QuadIn,
+                segment_eases: Some(vec![Ease::Linear]),
+                ..Default::default()
+            },
+        );
+        let mut player = KeyframeAnimationPlayer::new(add(&mut world, animation));
+        player.set_elapsed(0.5);
+        let root = world
+            .spawn()
+            .insert_bundle((Name::new("root"), Transform::default(), player))
+            .id();
+
+        // The first segment is overridden to be linear
+        run(&mut world);
+        assert_eq!(
+            world.get::<Transform>(root).unwrap().translation,
+            Vec3::X * 0.5
+        );
+
+        // The second segment falls back to the curve ease
+        world
+            .get_mut::<KeyframeAnimationPlayer<Transform>>(root)
+            .unwrap()
+            .set_elapsed(1.5);
+        run(&mut world);
+        assert_eq!(
+            world.get::<Transform>(root).unwrap().translation,
+            Vec3::X * 0.75
+        );
+    }
+
+    #[test]
+    fn hermite_curves_follow_tangents() {
+        let mut world = world();
+
+        let zero = || Keyframe(Transform::from_translation(Vec3::ZERO));
+        let mut animation = KeyframeAnimationClip::default();
+        animation.add_curve_to_path(
+            path(&["root"]),
+            KeyframeVariableCurve {
+                channels: TransformChannels::TRANSLATION.into(),
+                ..KeyframeVariableCurve::hermite(
+                    vec![0.0, 1.0],
+                    Keyframe::translation(vec![Vec3::ZERO, Vec3::X]),
+                    vec![
+                        KeyframeTangents {
+                            in_tangent: zero(),
+                            out_tangent: zero(),
+                        },
+                        KeyframeTangents {
+                            in_tangent: zero(),
+                            out_tangent: zero(),
+                        },
+                    ],
+                )
+            },
+        );
+        let mut player = KeyframeAnimationPlayer::new(add(&mut world, animation));
+        player.set_elapsed(0.25);
+        let root = world
+            .spawn()
+            .insert_bundle((
+                Name::new("root"),
+                Transform::from_scale(Vec3::splat(2.0)),
+                player,
+            ))
+            .id();
+
+        run(&mut world);
+
+        // Flat tangents give a smoothstep
+        let transform = world.get::<Transform>(root).unwrap();
+        assert!((transform.translation.x - 0.15625).abs() < 1e-5);
+        assert_eq!(transform.scale, Vec3::splat(2.0));
+    }
+
+    #[test]
+    fn root_without_component_animates_descendants() {
+        let mut world = world();
+
+        let mut animation = KeyframeAnimationClip::default();
+        animation.add_curve_to_path(
+            path(&["rig", "pivot", "bone"]),
+            translation_curve(Vec3::ZERO, Vec3::ONE),
+        );
+        let mut player = KeyframeAnimationPlayer::new(add(&mut world, animation));
+        player.set_elapsed(1.0);
+
+        let mut bone = None;
+        let root = world
+            .spawn()
+            .insert_bundle((Name::new("rig"), player))
+            .with_children(|p| {
+                // The intermediate entity doesn't have a `Transform` either
+                p.spawn().insert(Name::new("pivot")).with_children(|p| {
+                    bone = Some(
+                        p.spawn()
+                            .insert_bundle((Name::new("bone"), Transform::default()))
+                            .id(),
+                    );
+                });
+            })
+            .id();
+
+        run(&mut world);
+
+        assert!(world.get::<Transform>(root).is_none());
+        assert_eq!(
+            world.get::<Transform>(bone.unwrap()).unwrap().translation,
+            Vec3::ONE
+        );
     }
 }
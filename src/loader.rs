@@ -0,0 +1,623 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+#[cfg(feature = "ui")]
+use bevy::math::{Rect, Size};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    sprite::Anchor,
+    utils::BoxedFuture,
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    lerp::Lerp, marker::KeyframeMarker, Keyframe, KeyframeAnimationClip, KeyframeBlendMode,
+    KeyframeChannels, KeyframeClipUuid, KeyframeEntityPath, KeyframeVariableCurve,
+};
+
+/// Representation of a component in clip files. Keyframes of `T` can be
+/// serialized as soon as `T` has one.
+pub trait KeyframeRepr: Sized {
+    type Repr: Serialize + DeserializeOwned;
+
+    fn to_repr(&self) -> Self::Repr;
+
+    fn from_repr(repr: Self::Repr) -> Self;
+}
+
+impl<T: KeyframeRepr> Serialize for Keyframe<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.to_repr().serialize(serializer)
+    }
+}
+
+impl<'de, T: KeyframeRepr> Deserialize<'de> for Keyframe<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::Repr::deserialize(deserializer).map(|repr| Keyframe(T::from_repr(repr)))
+    }
+}
+
+/// Paths are serialized as the names of their parts separated by slashes,
+/// e.g. `"root/arm/hand"`.
+impl Serialize for KeyframeEntityPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let parts: Vec<_> = self.parts.iter().map(Name::as_str).collect();
+        parts.join("/").serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyframeEntityPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        Ok(KeyframeEntityPath::from(path.as_str()))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransformRepr {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for TransformRepr {
+    fn default() -> Self {
+        Transform::identity().to_repr()
+    }
+}
+
+impl KeyframeRepr for Transform {
+    type Repr = TransformRepr;
+
+    fn to_repr(&self) -> TransformRepr {
+        TransformRepr {
+            translation: self.translation,
+            rotation: self.rotation,
+            scale: self.scale,
+        }
+    }
+
+    fn from_repr(repr: TransformRepr) -> Self {
+        Transform {
+            translation: repr.translation,
+            rotation: repr.rotation,
+            scale: repr.scale,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Anchor")]
+enum AnchorRepr {
+    Center,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+    CenterLeft,
+    CenterRight,
+    TopLeft,
+    TopCenter,
+    TopRight,
+    Custom(Vec2),
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpriteRepr {
+    pub color: Color,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub custom_size: Option<Vec2>,
+    #[serde(with = "AnchorRepr")]
+    pub anchor: Anchor,
+}
+
+impl KeyframeRepr for Sprite {
+    type Repr = SpriteRepr;
+
+    fn to_repr(&self) -> SpriteRepr {
+        SpriteRepr {
+            color: self.color,
+            flip_x: self.flip_x,
+            flip_y: self.flip_y,
+            custom_size: self.custom_size,
+            anchor: self.anchor.clone(),
+        }
+    }
+
+    fn from_repr(repr: SpriteRepr) -> Self {
+        Sprite {
+            color: repr.color,
+            flip_x: repr.flip_x,
+            flip_y: repr.flip_y,
+            custom_size: repr.custom_size,
+            anchor: repr.anchor,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextureAtlasSpriteRepr {
+    pub color: Color,
+    pub index: usize,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub custom_size: Option<Vec2>,
+    #[serde(with = "AnchorRepr")]
+    pub anchor: Anchor,
+}
+
+impl KeyframeRepr for TextureAtlasSprite {
+    type Repr = TextureAtlasSpriteRepr;
+
+    fn to_repr(&self) -> TextureAtlasSpriteRepr {
+        TextureAtlasSpriteRepr {
+            color: self.color,
+            index: self.index,
+            flip_x: self.flip_x,
+            flip_y: self.flip_y,
+            custom_size: self.custom_size,
+            anchor: self.anchor.clone(),
+        }
+    }
+
+    fn from_repr(repr: TextureAtlasSpriteRepr) -> Self {
+        TextureAtlasSprite {
+            color: repr.color,
+            index: repr.index,
+            flip_x: repr.flip_x,
+            flip_y: repr.flip_y,
+            custom_size: repr.custom_size,
+            anchor: repr.anchor,
+        }
+    }
+}
+
+/// Missing sides are `Undefined`.
+#[cfg(feature = "ui")]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct RectRepr {
+    pub left: Val,
+    pub right: Val,
+    pub top: Val,
+    pub bottom: Val,
+}
+
+#[cfg(feature = "ui")]
+impl Default for RectRepr {
+    fn default() -> Self {
+        Rect::<Val>::default().into()
+    }
+}
+
+#[cfg(feature = "ui")]
+impl From<Rect<Val>> for RectRepr {
+    fn from(rect: Rect<Val>) -> Self {
+        RectRepr {
+            left: rect.left,
+            right: rect.right,
+            top: rect.top,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+#[cfg(feature = "ui")]
+impl From<RectRepr> for Rect<Val> {
+    fn from(repr: RectRepr) -> Self {
+        Rect {
+            left: repr.left,
+            right: repr.right,
+            top: repr.top,
+            bottom: repr.bottom,
+        }
+    }
+}
+
+/// Missing dimensions are `Undefined`.
+#[cfg(feature = "ui")]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct SizeRepr {
+    pub width: Val,
+    pub height: Val,
+}
+
+#[cfg(feature = "ui")]
+impl Default for SizeRepr {
+    fn default() -> Self {
+        Size::new(Val::Undefined, Val::Undefined).into()
+    }
+}
+
+#[cfg(feature = "ui")]
+impl From<Size<Val>> for SizeRepr {
+    fn from(size: Size<Val>) -> Self {
+        SizeRepr {
+            width: size.width,
+            height: size.height,
+        }
+    }
+}
+
+#[cfg(feature = "ui")]
+impl From<SizeRepr> for Size<Val> {
+    fn from(repr: SizeRepr) -> Self {
+        Size::new(repr.width, repr.height)
+    }
+}
+
+/// Only the attributes animated by [`StyleChannels`](crate::channels::StyleChannels)
+/// are in clip files.
+#[cfg(feature = "ui")]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleRepr {
+    pub position: RectRepr,
+    pub size: SizeRepr,
+    pub min_size: SizeRepr,
+    pub max_size: SizeRepr,
+    pub margin: RectRepr,
+    pub padding: RectRepr,
+    pub border: RectRepr,
+}
+
+#[cfg(feature = "ui")]
+impl Default for StyleRepr {
+    fn default() -> Self {
+        Style::default().to_repr()
+    }
+}
+
+#[cfg(feature = "ui")]
+impl KeyframeRepr for Style {
+    type Repr = StyleRepr;
+
+    fn to_repr(&self) -> StyleRepr {
+        StyleRepr {
+            position: self.position.into(),
+            size: self.size.into(),
+            min_size: self.min_size.into(),
+            max_size: self.max_size.into(),
+            margin: self.margin.into(),
+            padding: self.padding.into(),
+            border: self.border.into(),
+        }
+    }
+
+    fn from_repr(repr: StyleRepr) -> Self {
+        Style {
+            position: repr.position.into(),
+            size: repr.size.into(),
+            min_size: repr.min_size.into(),
+            max_size: repr.max_size.into(),
+            margin: repr.margin.into(),
+            padding: repr.padding.into(),
+            border: repr.border.into(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(feature = "ui")]
+impl KeyframeRepr for UiColor {
+    type Repr = Color;
+
+    fn to_repr(&self) -> Color {
+        self.0
+    }
+
+    fn from_repr(repr: Color) -> Self {
+        UiColor(repr)
+    }
+}
+
+#[cfg(feature = "ui")]
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextSectionRepr {
+    pub color: Color,
+    pub font_size: f32,
+}
+
+/// The sections of a text, with the attributes animated by
+/// [`TextChannels`](crate::channels::TextChannels).
+#[cfg(feature = "ui")]
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextRepr {
+    pub sections: Vec<TextSectionRepr>,
+}
+
+#[cfg(feature = "ui")]
+impl KeyframeRepr for Text {
+    type Repr = TextRepr;
+
+    fn to_repr(&self) -> TextRepr {
+        TextRepr {
+            sections: self
+                .sections
+                .iter()
+                .map(|section| TextSectionRepr {
+                    color: section.style.color,
+                    font_size: section.style.font_size,
+                })
+                .collect(),
+        }
+    }
+
+    fn from_repr(repr: TextRepr) -> Self {
+        Text {
+            sections: repr
+                .sections
+                .into_iter()
+                .map(|section| TextSection {
+                    value: String::new(),
+                    style: TextStyle {
+                        color: section.color,
+                        font_size: section.font_size,
+                        ..Default::default()
+                    },
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Content of a clip file.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "Keyframe<T>: Serialize, KeyframeChannels<T>: Serialize",
+    deserialize = "Keyframe<T>: DeserializeOwned, KeyframeChannels<T>: DeserializeOwned"
+))]
+struct KeyframeClipFile<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    /// Curves of each path.
+    curves: HashMap<KeyframeEntityPath, Vec<KeyframeVariableCurve<T>>>,
+    /// Markers of the clip, none by default.
+    #[serde(default)]
+    markers: Vec<KeyframeMarker>,
+    /// How the values of the clip are applied, overriding them by default.
+    #[serde(default)]
+    blend_mode: KeyframeBlendMode,
+}
+
+/// Parse a clip from the content of a RON file, or a JSON one if `json` is
+/// set.
+pub fn parse_clip<T>(bytes: &[u8], json: bool) -> Result<KeyframeAnimationClip<T>, anyhow::Error>
+where
+    Keyframe<T>: Lerp<T> + DeserializeOwned,
+    KeyframeChannels<T>: DeserializeOwned,
+{
+    let file: KeyframeClipFile<T> = if json {
+        serde_json::from_slice(bytes)?
+    } else {
+        ron::de::from_bytes(bytes)?
+    };
+    let mut clip = KeyframeAnimationClip::default();
+    for (path, curves) in file.curves {
+        for curve in curves {
+            clip.try_add_curve_to_path(path.clone(), curve)?;
+        }
+    }
+    for marker in file.markers {
+        clip.add_marker(marker);
+    }
+    clip.set_blend_mode(file.blend_mode);
+    Ok(clip)
+}
+
+/// Loads [`KeyframeAnimationClip<T>`]s from RON or JSON files, with one curve
+/// list per entity path and the optional markers and blend mode of the clip:
+///
+/// ```ron
+/// (
+///     curves: {
+///         "coin": [(
+///             keyframe_timestamps: [0.0, 0.1, 0.2],
+///             keyframes: [(index: 0), (index: 1), (index: 2)],
+///             channels: ["index"],
+///         )],
+///     },
+///     markers: [(timestamp: 0.1, name: "shine")],
+///     blend_mode: Override,
+/// )
+/// ```
+///
+/// Players resolve their clip every frame, so an edited file is picked up on
+/// hot reload without resetting their elapsed time.
+pub struct KeyframeClipLoader<T> {
+    extensions: &'static [&'static str],
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> KeyframeClipLoader<T> {
+    /// Create a loader for the files with the given extensions. Files ending
+    /// with `.json` are parsed as JSON, the others as RON.
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        Self {
+            extensions,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> AssetLoader for KeyframeClipLoader<T>
+where
+    T: KeyframeClipUuid + Send + Sync + 'static,
+    Keyframe<T>: Lerp<T> + DeserializeOwned,
+    KeyframeChannels<T>: DeserializeOwned,
+{
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let json = load_context.path().extension() == Some("json".as_ref());
+            let clip = parse_clip::<T>(bytes, json)?;
+            load_context.set_default_asset(LoadedAsset::new(clip));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        channels::{RotationInterpolation, TextureAtlasSpriteChannels, TransformChannels},
+        ease::Ease,
+    };
+
+    #[test]
+    fn parse_ron_and_json_clips() {
+        let ron = r#"(
+            curves: {
+                "coin": [(
+                    keyframe_timestamps: [0.0, 0.1, 0.2],
+                    keyframes: [(index: 0), (index: 1), (index: 2)],
+                    channels: ["index"],
+                    ease: Step,
+                )],
+            },
+        )"#;
+        let json = r#"{
+            "curves": {
+                "coin": [{
+                    "keyframe_timestamps": [0.0, 0.1, 0.2],
+                    "keyframes": [{ "index": 0 }, { "index": 1 }, { "index": 2 }],
+                    "channels": ["index"],
+                    "ease": "Step"
+                }]
+            }
+        }"#;
+        for clip in [
+            parse_clip::<TextureAtlasSprite>(ron.as_bytes(), false).unwrap(),
+            parse_clip::<TextureAtlasSprite>(json.as_bytes(), true).unwrap(),
+        ] {
+            assert_eq!(clip.duration(), 0.2);
+            assert!(clip.markers().is_empty());
+            assert_eq!(clip.blend_mode(), KeyframeBlendMode::Override);
+            let path = KeyframeEntityPath {
+                parts: vec![Name::new("coin")],
+            };
+            let curve = &clip.curves()[&path][0];
+            assert_eq!(curve.channels, TextureAtlasSpriteChannels::INDEX);
+            assert!(matches!(curve.ease, Ease::Step));
+            assert_eq!(curve.keyframes[2].0.index, 2);
+        }
+    }
+
+    #[test]
+    fn transform_keyframes_round_trip() {
+        let ron = r#"(
+            curves: {
+                "planet/satellite": [(
+                    keyframe_timestamps: [0.0, 1.0],
+                    keyframes: [(translation: (1.0, 0.0, 0.0)), (scale: (2.0, 2.0, 2.0))],
+                    channels: ["translation", "scale"],
+                    interpolation: Linear,
+                )],
+            },
+        )"#;
+        let clip = parse_clip::<Transform>(ron.as_bytes(), false).unwrap();
+        let (path, curves) = clip.curves().iter().next().unwrap();
+        assert_eq!(path.parts, [Name::new("planet"), Name::new("satellite")]);
+        assert_eq!(curves[0].keyframes[0].0.scale, Vec3::ONE);
+        assert_eq!(curves[0].keyframes[1].0.scale, Vec3::splat(2.0));
+
+        let serialized = ron::to_string(&curves[0]).unwrap();
+        let curve: KeyframeVariableCurve<Transform> = ron::from_str(&serialized).unwrap();
+        assert_eq!(curve.keyframes[0].0.translation, Vec3::X);
+        assert_eq!(curve.channels, curves[0].channels);
+    }
+
+    #[test]
+    fn markers_and_blend_mode_round_trip() {
+        let file = KeyframeClipFile::<Transform> {
+            curves: HashMap::from([(
+                KeyframeEntityPath::from("hero"),
+                vec![KeyframeVariableCurve {
+                    keyframe_timestamps: vec![0.0, 1.0],
+                    keyframes: Keyframe::translation(vec![Vec3::ZERO, Vec3::Y]),
+                    ..Default::default()
+                }],
+            )]),
+            markers: vec![KeyframeMarker {
+                timestamp: 1.5,
+                name: "land".to_string(),
+                payload: "dust".to_string(),
+            }],
+            blend_mode: KeyframeBlendMode::Additive,
+        };
+        for clip in [
+            parse_clip::<Transform>(ron::to_string(&file).unwrap().as_bytes(), false).unwrap(),
+            parse_clip::<Transform>(&serde_json::to_vec(&file).unwrap(), true).unwrap(),
+        ] {
+            assert_eq!(clip.markers(), file.markers);
+            assert_eq!(clip.blend_mode(), KeyframeBlendMode::Additive);
+            // Markers after the last keyframe extend the clip
+            assert_eq!(clip.duration(), 1.5);
+            let curve = &clip.curves()[&KeyframeEntityPath::from("hero")][0];
+            assert_eq!(curve.keyframes[1].0.translation, Vec3::Y);
+        }
+    }
+
+    #[test]
+    fn rotation_interpolation_is_read_with_the_channels() {
+        let ron = r#"(
+            keyframe_timestamps: [0.0, 1.0],
+            keyframes: [(), ()],
+            channels: (channels: ["rotation"], rotation_interpolation: Nlerp),
+        )"#;
+        let curve: KeyframeVariableCurve<Transform> = ron::from_str(ron).unwrap();
+        assert_eq!(curve.channels.channels, TransformChannels::ROTATION);
+        assert_eq!(
+            curve.channels.rotation_interpolation,
+            RotationInterpolation::Nlerp
+        );
+
+        let serialized = ron::to_string(&curve).unwrap();
+        let curve: KeyframeVariableCurve<Transform> = ron::from_str(&serialized).unwrap();
+        assert_eq!(
+            curve.channels.rotation_interpolation,
+            RotationInterpolation::Nlerp
+        );
+    }
+
+    #[cfg(feature = "ui")]
+    #[test]
+    fn parse_style_clips() {
+        let ron = r#"(
+            curves: {
+                "menu": [(
+                    keyframe_timestamps: [0.0, 0.5],
+                    keyframes: [
+                        (position: (left: Percent(-100.0))),
+                        (position: (left: Percent(0.0)), size: (width: Px(200.0))),
+                    ],
+                    channels: ["position"],
+                )],
+            },
+        )"#;
+        let clip = parse_clip::<Style>(ron.as_bytes(), false).unwrap();
+        let curve = &clip.curves()[&KeyframeEntityPath::from("menu")][0];
+        assert_eq!(curve.keyframes[0].0.position.left, Val::Percent(-100.0));
+        assert_eq!(curve.keyframes[0].0.position.top, Val::Undefined);
+        assert_eq!(curve.keyframes[1].0.size.width, Val::Px(200.0));
+        // Missing from the size, unlike the size itself
+        assert_eq!(curve.keyframes[1].0.size.height, Val::Undefined);
+        assert_eq!(curve.keyframes[0].0.size.height, Val::Auto);
+    }
+}
@@ -0,0 +1,141 @@
+use bevy::{asset::HandleId, prelude::*};
+
+use crate::repeat::{elapsed_times, RepeatMode};
+
+/// Named point in time of a clip, used to trigger gameplay effects such as
+/// footstep sounds or hitboxes.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyframeMarker {
+    /// Time of the marker in the clip, in seconds.
+    pub timestamp: f32,
+    /// Name of the marker.
+    pub name: String,
+    /// Data passed along with the marker, free for the game to interpret.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub payload: String,
+}
+
+/// Event sent when the elapsed time of a player crosses a [`KeyframeMarker`]
+/// of its clip.
+#[derive(Clone, Debug)]
+pub struct KeyframeMarkerEvent {
+    /// Entity of the player.
+    pub entity: Entity,
+    /// Clip the marker belongs to.
+    pub clip: HandleId,
+    /// Name of the marker.
+    pub name: String,
+    /// Payload of the marker.
+    pub payload: String,
+}
+
+/// Markers crossed when going from `from` to `to`, two times elapsed playing
+/// a clip of `duration`. Each marker is crossed once per loop when repeating,
+/// so a long step can cross the same marker several times.
+///
+/// The crossed range includes `from` but not `to`, so that a marker at the
+/// start of a clip is crossed on the first step, and a marker is never
+/// crossed twice by consecutive steps. The markers are returned in the order
+/// they're crossed, backward if `to` is before `from`.
+pub(crate) fn crossed_markers(
+    markers: &[KeyframeMarker],
+    from: f32,
+    to: f32,
+    duration: f32,
+    repeat_mode: RepeatMode,
+) -> Vec<&KeyframeMarker> {
+    // Markers with the time they're crossed at and their loop, so that the
+    // end of a loop comes before the start of the next one
+    let mut crossed = Vec::new();
+    for marker in markers {
+        for (part, elapsed) in elapsed_times(marker.timestamp, from, to, duration, repeat_mode) {
+            if (from <= to && from <= elapsed && elapsed < to)
+                || (from > to && to < elapsed && elapsed <= from)
+            {
+                crossed.push((elapsed, part, marker));
+            }
+        }
+    }
+    crossed.sort_by(|(a, a_part, _), (b, b_part, _)| a.total_cmp(b).then(a_part.cmp(b_part)));
+    if from > to {
+        crossed.reverse();
+    }
+    crossed.into_iter().map(|(_, _, marker)| marker).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markers() -> Vec<KeyframeMarker> {
+        ["start", "step", "end"]
+            .into_iter()
+            .zip([0.0, 0.5, 1.0])
+            .map(|(name, timestamp)| KeyframeMarker {
+                timestamp,
+                name: name.to_string(),
+                payload: String::new(),
+            })
+            .collect()
+    }
+
+    fn names(markers: Vec<&KeyframeMarker>) -> Vec<&str> {
+        markers.iter().map(|marker| marker.name.as_str()).collect()
+    }
+
+    #[test]
+    fn markers_are_crossed_once() {
+        let markers = markers();
+        assert_eq!(
+            names(crossed_markers(&markers, 0.0, 0.5, 1.0, RepeatMode::Once)),
+            ["start"]
+        );
+        assert_eq!(
+            names(crossed_markers(&markers, 0.5, 1.5, 1.0, RepeatMode::Once)),
+            ["step", "end"]
+        );
+        assert!(crossed_markers(&markers, 1.5, 2.0, 1.0, RepeatMode::Once).is_empty());
+    }
+
+    #[test]
+    fn repeated_markers_are_crossed_every_loop() {
+        let markers = markers();
+        // The end of a loop is the start of the next one
+        assert_eq!(
+            names(crossed_markers(&markers, 0.25, 2.75, 1.0, RepeatMode::Loop)),
+            ["step", "end", "start", "step", "end", "start", "step"]
+        );
+        // Backward, from the second loop into the first one
+        assert_eq!(
+            names(crossed_markers(&markers, 1.25, 0.25, 1.0, RepeatMode::Loop)),
+            ["start", "end", "step"]
+        );
+    }
+
+    #[test]
+    fn ping_pong_markers_are_crossed_both_ways() {
+        let markers = markers();
+        // The marker at the turn is only crossed once
+        assert_eq!(
+            names(crossed_markers(
+                &markers,
+                0.25,
+                2.25,
+                1.0,
+                RepeatMode::PingPong
+            )),
+            ["step", "end", "step", "start"]
+        );
+        assert_eq!(
+            names(crossed_markers(
+                &markers,
+                0.25,
+                5.0,
+                1.0,
+                RepeatMode::PingPongCount(1)
+            )),
+            ["step", "end", "step", "start"]
+        );
+    }
+}
@@ -0,0 +1,515 @@
+use std::{any::TypeId, sync::Arc};
+
+use bevy::{
+    ecs::{reflect::ReflectComponent, system::SystemState},
+    prelude::*,
+    reflect::{GetPath, TypeRegistry, TypeRegistryInternal},
+    utils::HashMap,
+};
+
+use crate::{
+    ease::Ease,
+    find_segment,
+    implemented::interpolate_rotation,
+    lerp::Lerp,
+    repeat::{clip_elapsed, RepeatMode},
+    target::{HierarchyChanges, KeyframeTargetCache},
+    Keyframe, KeyframeEntityPath,
+};
+
+/// Describes how a reflected field of a component should be animated.
+///
+/// `keyframe_timestamps` and `keyframes` should have the same length.
+pub struct PropertyCurve {
+    /// Name of the animated component type, e.g. `Transform`. Both the short
+    /// and the full type names are accepted.
+    pub component: String,
+    /// Path of the animated field in the component, e.g. `translation.x`. An
+    /// empty path animates the whole component.
+    pub field: String,
+    /// Timestamp for each of the keyframes.
+    pub keyframe_timestamps: Vec<f32>,
+    /// List of the keyframes, they should have the type of the field.
+    pub keyframes: Vec<Box<dyn Reflect>>,
+    /// Easing applied to every segment of the curve.
+    pub ease: Ease,
+    /// Easing of each segment, overriding `ease`.
+    pub segment_eases: Option<Vec<Ease>>,
+}
+
+impl PropertyCurve {
+    /// Create a curve animating `property`, the name of a component followed
+    /// by the path of one of its fields, e.g. `Transform.translation.x` or
+    /// `PointLight.intensity`.
+    pub fn new<V: Reflect>(
+        property: &str,
+        keyframe_timestamps: Vec<f32>,
+        keyframes: Vec<V>,
+    ) -> Self {
+        let (component, field) = property.split_once('.').unwrap_or((property, ""));
+        Self {
+            component: component.to_string(),
+            field: field.to_string(),
+            keyframe_timestamps,
+            keyframes: keyframes
+                .into_iter()
+                .map(|keyframe| Box::new(keyframe) as Box<dyn Reflect>)
+                .collect(),
+            ease: Ease::Linear,
+            segment_eases: None,
+        }
+    }
+
+    /// Easing of the segment starting at the keyframe `segment`.
+    pub fn segment_ease(&self, segment: usize) -> Ease {
+        self.segment_eases
+            .as_ref()
+            .and_then(|eases| eases.get(segment))
+            .copied()
+            .unwrap_or(self.ease)
+    }
+
+    /// Sample the curve at `elapsed`. Returns `None` when the curve isn't
+    /// started yet or is finished.
+    pub fn sample(
+        &self,
+        elapsed: f32,
+        interpolators: &PropertyInterpolators,
+    ) -> Option<Box<dyn Reflect>> {
+        if self.keyframe_timestamps.len() == 1 {
+            return Some(self.keyframes.first()?.clone_value());
+        }
+        let (step_start, progress) = find_segment(&self.keyframe_timestamps, elapsed)?;
+        Some(interpolators.interpolate(
+            &**self.keyframes.get(step_start)?,
+            &**self.keyframes.get(step_start + 1)?,
+            self.segment_ease(step_start).apply(progress),
+        ))
+    }
+}
+
+/// An animation of reflected fields, which can target any registered
+/// component with [`ReflectComponent`] on many entities at once.
+#[derive(Default)]
+pub struct PropertyAnimationClip {
+    curves: HashMap<KeyframeEntityPath, Vec<PropertyCurve>>,
+    duration: f32,
+}
+
+impl PropertyAnimationClip {
+    /// Hashmap of the [`PropertyCurve`]s per [`KeyframeEntityPath`].
+    #[inline]
+    pub fn curves(&self) -> &HashMap<KeyframeEntityPath, Vec<PropertyCurve>> {
+        &self.curves
+    }
+
+    /// Duration of the clip, represented in seconds
+    #[inline]
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    /// Add a [`PropertyCurve`] to a [`KeyframeEntityPath`].
+    pub fn add_curve_to_path(&mut self, path: KeyframeEntityPath, curve: PropertyCurve) {
+        // Update the duration of the animation by this curve duration if it's longer
+        self.duration = self
+            .duration
+            .max(*curve.keyframe_timestamps.last().unwrap_or(&0.0));
+        self.curves.entry(path).or_default().push(curve);
+    }
+}
+
+/// Plays a [`PropertyAnimationClip`], using the entity as the animation root.
+#[derive(Component)]
+pub struct PropertyAnimationPlayer {
+    paused: bool,
+    repeat_mode: RepeatMode,
+    speed: f32,
+    elapsed: f32,
+    animation_clip: Arc<PropertyAnimationClip>,
+    target_cache: KeyframeTargetCache,
+}
+
+impl PropertyAnimationPlayer {
+    pub fn new(animation_clip: PropertyAnimationClip) -> Self {
+        Self {
+            paused: false,
+            repeat_mode: RepeatMode::Once,
+            speed: 1.0,
+            elapsed: 0.0,
+            animation_clip: Arc::new(animation_clip),
+            target_cache: KeyframeTargetCache::default(),
+        }
+    }
+
+    /// Start playing an animation, resetting state of the player
+    pub fn play(&mut self, animation_clip: PropertyAnimationClip) -> &mut Self {
+        let target_cache = std::mem::take(&mut self.target_cache);
+        *self = Self::new(animation_clip);
+        self.target_cache = target_cache;
+        self
+    }
+
+    /// Set the animation to repeat
+    pub fn repeat(&mut self) -> &mut Self {
+        self.repeat_mode = RepeatMode::Loop;
+        self
+    }
+
+    /// Stop the animation from repeating
+    pub fn stop_repeating(&mut self) -> &mut Self {
+        self.repeat_mode = RepeatMode::Once;
+        self
+    }
+
+    /// How the animation repeats
+    pub fn repeat_mode(&self) -> RepeatMode {
+        self.repeat_mode
+    }
+
+    /// Set how the animation repeats
+    pub fn set_repeat_mode(&mut self, repeat_mode: RepeatMode) -> &mut Self {
+        self.repeat_mode = repeat_mode;
+        self
+    }
+
+    /// Pause the animation
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Unpause the animation
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Is the animation paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Speed of the animation playback
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Set the speed of the animation playback
+    pub fn set_speed(&mut self, speed: f32) -> &mut Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Time elapsed playing the animation
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Seek to a specific time in the animation
+    pub fn set_elapsed(&mut self, elapsed: f32) -> &mut Self {
+        self.elapsed = elapsed;
+        self
+    }
+}
+
+type Interpolator =
+    Box<dyn Fn(&dyn Reflect, &dyn Reflect, f32) -> Option<Box<dyn Reflect>> + Send + Sync>;
+
+/// Interpolation functions of the field types animated by
+/// [`PropertyCurve`]s. Types without an interpolator step from one keyframe
+/// to the next, which is what enums and `bool`s need.
+pub struct PropertyInterpolators {
+    interpolators: HashMap<TypeId, Interpolator>,
+}
+
+impl Default for PropertyInterpolators {
+    fn default() -> Self {
+        let mut interpolators = Self {
+            interpolators: HashMap::default(),
+        };
+        interpolators
+            .register::<f32>(|from, to, scalar| from + (to - from) * scalar)
+            .register::<f64>(|from, to, scalar| from + (to - from) * scalar as f64)
+            .register::<Vec2>(|from, to, scalar| from.lerp(*to, scalar))
+            .register::<Vec3>(|from, to, scalar| from.lerp(*to, scalar))
+            .register::<Vec4>(|from, to, scalar| from.lerp(*to, scalar))
+            .register::<Quat>(|from, to, scalar| interpolate_rotation(*from, *to, scalar, false))
+            .register::<Color>(|from, to, scalar| {
+                Keyframe(*from).lerp(&Keyframe(*to), scalar, from, &()).0
+            });
+        interpolators
+    }
+}
+
+impl PropertyInterpolators {
+    /// Register the interpolation function of the type `V`.
+    pub fn register<V: Reflect>(&mut self, interpolate: fn(&V, &V, f32) -> V) -> &mut Self {
+        self.interpolators.insert(
+            TypeId::of::<V>(),
+            Box::new(move |from, to, scalar| {
+                let from = from.downcast_ref::<V>()?;
+                let to = to.downcast_ref::<V>()?;
+                Some(Box::new(interpolate(from, to, scalar)))
+            }),
+        );
+        self
+    }
+
+    /// Interpolate between two values of the same type.
+    pub fn interpolate(
+        &self,
+        from: &dyn Reflect,
+        to: &dyn Reflect,
+        scalar: f32,
+    ) -> Box<dyn Reflect> {
+        self.interpolators
+            .get(&from.type_id())
+            .and_then(|interpolate| interpolate(from, to, scalar))
+            .unwrap_or_else(|| {
+                if scalar < 1.0 {
+                    from.clone_value()
+                } else {
+                    to.clone_value()
+                }
+            })
+    }
+}
+
+/// Get the field at `path` in `value`. Components of vectors can be accessed
+/// with `x`, `y`, `z` and `w`, even though vectors are reflected as values.
+fn field_mut<'a>(value: &'a mut dyn Reflect, path: &str) -> Option<&'a mut dyn Reflect> {
+    if path.is_empty() {
+        return Some(value);
+    }
+    if value.path(path).is_ok() {
+        return value.path_mut(path).ok();
+    }
+    let (parent, component) = path.rsplit_once('.').unwrap_or(("", path));
+    let parent = field_mut(value, parent)?;
+    let index = ["x", "y", "z", "w"].iter().position(|c| *c == component)?;
+    let any = parent.any_mut();
+    let component: &mut f32 = if any.is::<Vec2>() {
+        any.downcast_mut::<Vec2>()?.as_mut().get_mut(index)?
+    } else if any.is::<Vec3>() {
+        any.downcast_mut::<Vec3>()?.as_mut().get_mut(index)?
+    } else {
+        any.downcast_mut::<Vec4>()?.as_mut().get_mut(index)?
+    };
+    Some(component)
+}
+
+/// Queries resolving the paths of the players, kept between runs so that
+/// the hierarchy changes since the last run are detected.
+struct PropertyTargetQueries(
+    SystemState<(
+        Query<'static, 'static, &'static Children>,
+        Query<'static, 'static, &'static Name>,
+        HierarchyChanges<'static, 'static>,
+    )>,
+);
+
+impl FromWorld for PropertyTargetQueries {
+    fn from_world(world: &mut World) -> Self {
+        Self(SystemState::new(world))
+    }
+}
+
+/// Apply a sampled `curve` to the `target` entity, returning the problem to
+/// report if it can't be applied.
+fn apply_curve(
+    world: &mut World,
+    registry: &TypeRegistryInternal,
+    interpolators: &PropertyInterpolators,
+    target: Entity,
+    curve: &PropertyCurve,
+    elapsed: f32,
+) -> Result<(), String> {
+    let registration = registry
+        .get_with_short_name(&curve.component)
+        .or_else(|| registry.get_with_name(&curve.component))
+        .ok_or_else(|| format!("Component {} is not registered", curve.component))?;
+    let reflect_component = registration
+        .data::<ReflectComponent>()
+        .ok_or_else(|| format!("Component {} doesn't reflect `Component`", curve.component))?;
+    let value = match curve.sample(elapsed, interpolators) {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    let mut component = match reflect_component.reflect_component(world, target) {
+        Some(component) => component.clone_value(),
+        // The entity exists but doesn't have the animated component
+        None => return Ok(()),
+    };
+    match field_mut(&mut *component, &curve.field) {
+        Some(field) if field.type_name() == value.type_name() => field.apply(&*value),
+        Some(field) => {
+            return Err(format!(
+                "Field {}.{} is a {}, not a {}",
+                curve.component,
+                curve.field,
+                field.type_name(),
+                value.type_name()
+            ));
+        }
+        None => {
+            return Err(format!(
+                "Field {}.{} not found",
+                curve.component, curve.field
+            ))
+        }
+    }
+    reflect_component.apply_component(world, target, &*component);
+    Ok(())
+}
+
+/// System that will play all [`PropertyAnimationPlayer`]s, using their entity
+/// as the animation root. It needs exclusive access to the world to animate
+/// any component through reflection.
+pub fn property_animation_player(world: &mut World) {
+    let delta = world.resource::<Time>().delta_seconds();
+    let mut animations = Vec::new();
+    let mut players = world.query::<(Entity, &mut PropertyAnimationPlayer)>();
+    for (entity, mut player) in players.iter_mut(world) {
+        // Continue if paused unless the player was changed, so that the
+        // animation is updated when seeking in pause
+        if player.paused && !player.is_changed() {
+            continue;
+        }
+        if !player.paused {
+            player.elapsed += delta * player.speed;
+        }
+        let elapsed = clip_elapsed(
+            player.elapsed,
+            player.animation_clip.duration,
+            player.repeat_mode,
+        );
+        let target_cache = std::mem::take(&mut player.target_cache);
+        animations.push((entity, elapsed, player.animation_clip.clone(), target_cache));
+    }
+
+    // Resolve the paths first, the curves are then applied with exclusive
+    // access to the world
+    let mut targets = Vec::new();
+    world.init_resource::<PropertyTargetQueries>();
+    world.resource_scope(|world, mut queries: Mut<PropertyTargetQueries>| {
+        let (children, names, hierarchy_changes) = queries.0.get(world);
+        let hierarchy_changed = !hierarchy_changes.is_empty();
+        for (index, (root, _, clip, target_cache)) in animations.iter_mut().enumerate() {
+            if hierarchy_changed {
+                target_cache.clear();
+            }
+            for path in clip.curves.keys() {
+                if let Some(target) = target_cache.get(*root, path, &children, &names) {
+                    targets.push((index, target, path.clone()));
+                }
+            }
+        }
+    });
+
+    let registry = world.resource::<TypeRegistry>().clone();
+    let registry = registry.read();
+    world.resource_scope(|world, interpolators: Mut<PropertyInterpolators>| {
+        for (index, target, path) in targets {
+            let (_, elapsed, clip, target_cache) = &mut animations[index];
+            for curve in &clip.curves[&path] {
+                let applied =
+                    apply_curve(world, &registry, &interpolators, target, curve, *elapsed);
+                if let Err(message) = applied {
+                    target_cache.warn_once(message);
+                }
+            }
+        }
+    });
+
+    for (entity, _, _, target_cache) in animations {
+        if let Some(mut player) = world.get_mut::<PropertyAnimationPlayer>(entity) {
+            player.target_cache = target_cache;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::schedule::SystemStage;
+
+    use super::*;
+
+    #[test]
+    fn reflected_fields_are_animated() {
+        let mut world = World::default();
+        world.insert_resource(Time::default());
+        world.init_resource::<PropertyInterpolators>();
+        let registry = TypeRegistry::default();
+        registry.write().register::<Transform>();
+        world.insert_resource(registry);
+
+        let mut animation = PropertyAnimationClip::default();
+        let root = KeyframeEntityPath {
+            parts: vec![Name::new("root")],
+        };
+        animation.add_curve_to_path(
+            root.clone(),
+            PropertyCurve::new(
+                "Transform.translation",
+                vec![0.0, 1.0],
+                vec![Vec3::ZERO, Vec3::X],
+            ),
+        );
+        animation.add_curve_to_path(
+            root,
+            PropertyCurve::new("Transform.scale.y", vec![0.0, 2.0], vec![1.0f32, 3.0]),
+        );
+        let mut player = PropertyAnimationPlayer::new(animation);
+        player.set_elapsed(0.5);
+        let root = world
+            .spawn()
+            .insert_bundle((Name::new("root"), Transform::default(), player))
+            .id();
+
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(property_animation_player.exclusive_system());
+        stage.run(&mut world);
+
+        let transform = world.get::<Transform>(root).unwrap();
+        assert_eq!(transform.translation, Vec3::X * 0.5);
+        assert_eq!(transform.scale, Vec3::new(1.0, 1.5, 1.0));
+    }
+
+    #[test]
+    fn targets_are_resolved_again_after_renames() {
+        let mut world = World::default();
+        world.insert_resource(Time::default());
+        world.init_resource::<PropertyInterpolators>();
+        let registry = TypeRegistry::default();
+        registry.write().register::<Transform>();
+        world.insert_resource(registry);
+
+        let mut animation = PropertyAnimationClip::default();
+        animation.add_curve_to_path(
+            KeyframeEntityPath::from("root/arm"),
+            PropertyCurve::new("Transform.translation", vec![0.0], vec![Vec3::X]),
+        );
+        // Unknown field, reported once without stopping the other curves
+        animation.add_curve_to_path(
+            KeyframeEntityPath::from("root/arm"),
+            PropertyCurve::new("Transform.position", vec![0.0], vec![Vec3::Y]),
+        );
+        let arm = world
+            .spawn()
+            .insert_bundle((Name::new("leg"), Transform::default()))
+            .id();
+        world
+            .spawn()
+            .insert_bundle((Name::new("root"), PropertyAnimationPlayer::new(animation)))
+            .push_children(&[arm]);
+
+        // Persistent, so that only the changes since the last run are seen
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(property_animation_player.exclusive_system());
+        stage.run(&mut world);
+        assert_eq!(world.get::<Transform>(arm).unwrap().translation, Vec3::ZERO);
+
+        world.get_mut::<Name>(arm).unwrap().set("arm");
+        stage.run(&mut world);
+        assert_eq!(world.get::<Transform>(arm).unwrap().translation, Vec3::X);
+    }
+}
@@ -0,0 +1,56 @@
+use bevy::{asset::Asset, prelude::*};
+
+use crate::{lerp::Lerp, repeat::RepeatMode, Keyframe, KeyframeAnimationClip};
+
+/// Clip queued on a [`KeyframeAnimationPlayer`](crate::KeyframeAnimationPlayer),
+/// started once the clips before it finish, e.g. the recover and idle clips
+/// after an attack.
+pub struct KeyframeQueuedClip<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    /// Clip to play.
+    pub animation_clip: Handle<KeyframeAnimationClip<T>>,
+    /// How the clip repeats. A clip repeating forever never starts the next
+    /// one.
+    pub repeat_mode: RepeatMode,
+    /// Speed of the clip playback.
+    pub speed: f32,
+    /// Duration of the crossfade from the previous clip, in seconds. The
+    /// clips are switched at once if it's zero.
+    pub transition: f32,
+}
+
+impl<T> KeyframeQueuedClip<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    pub fn new(animation_clip: Handle<KeyframeAnimationClip<T>>) -> Self {
+        Self {
+            animation_clip,
+            repeat_mode: RepeatMode::Once,
+            speed: 1.0,
+            transition: 0.0,
+        }
+    }
+
+    /// Set how the clip repeats
+    pub fn with_repeat_mode(mut self, repeat_mode: RepeatMode) -> Self {
+        self.repeat_mode = repeat_mode;
+        self
+    }
+
+    /// Set the speed of the clip playback
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Set the duration of the crossfade from the previous clip
+    pub fn with_transition(mut self, transition: f32) -> Self {
+        self.transition = transition;
+        self
+    }
+}
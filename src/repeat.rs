@@ -0,0 +1,195 @@
+/// How a player repeats its clip.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RepeatMode {
+    /// Play the clip once.
+    #[default]
+    Once,
+    /// Loop over the clip forever.
+    Loop,
+    /// Loop over the clip the given number of times.
+    LoopCount(u32),
+    /// Play the clip forward then in reverse, forever.
+    PingPong,
+    /// Play the clip forward then in reverse, the given number of times.
+    PingPongCount(u32),
+}
+
+/// What a player shows once it's done playing its clip.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FinishMode {
+    /// Keep the last frame.
+    #[default]
+    Hold,
+    /// Go back to the first frame.
+    Reset,
+}
+
+impl RepeatMode {
+    /// Time elapsed playing a whole loop, forward then in reverse when
+    /// ping-ponging.
+    fn period(&self, duration: f32) -> f32 {
+        match self {
+            RepeatMode::PingPong | RepeatMode::PingPongCount(_) => 2.0 * duration,
+            _ => duration,
+        }
+    }
+
+    /// Number of loops played before finishing, if it's finite.
+    fn loops(&self) -> Option<u32> {
+        match self {
+            RepeatMode::Once => Some(1),
+            RepeatMode::LoopCount(count) | RepeatMode::PingPongCount(count) => Some(*count),
+            RepeatMode::Loop | RepeatMode::PingPong => None,
+        }
+    }
+
+    /// Time elapsed when the clip is done playing, if it ever is.
+    fn end(&self, duration: f32) -> Option<f32> {
+        self.loops()
+            .map(|loops| loops as f32 * self.period(duration))
+    }
+}
+
+/// Time in the clip for the time `elapsed` playing it.
+pub(crate) fn clip_elapsed(elapsed: f32, duration: f32, repeat_mode: RepeatMode) -> f32 {
+    if duration <= 0.0 {
+        return 0.0;
+    }
+    let elapsed = match repeat_mode.end(duration) {
+        Some(end) => elapsed.clamp(0.0, end),
+        None => elapsed,
+    };
+    let period = repeat_mode.period(duration);
+    // Keep the end of the last loop instead of wrapping to its start
+    let time = if repeat_mode.end(duration) == Some(elapsed) && elapsed > 0.0 {
+        period
+    } else {
+        elapsed.rem_euclid(period)
+    };
+    if time > duration {
+        // Going in reverse when ping-ponging
+        period - time
+    } else {
+        time
+    }
+}
+
+/// Index of the loop played at `elapsed`.
+fn loop_index(elapsed: f32, duration: f32, repeat_mode: RepeatMode) -> i64 {
+    let index = (elapsed / repeat_mode.period(duration)).floor() as i64;
+    match repeat_mode.loops() {
+        Some(loops) => index.clamp(0, (loops as i64 - 1).max(0)),
+        None => index,
+    }
+}
+
+/// Number of loops started when going from `from` to `to`, two times elapsed
+/// playing a clip of `duration`.
+pub(crate) fn crossed_loops(from: f32, to: f32, duration: f32, repeat_mode: RepeatMode) -> u32 {
+    if duration <= 0.0 {
+        return 0;
+    }
+    (loop_index(to, duration, repeat_mode) - loop_index(from, duration, repeat_mode)).unsigned_abs()
+        as u32
+}
+
+/// Whether a player is done playing a clip of `duration`.
+pub(crate) fn is_finished(
+    elapsed: f32,
+    duration: f32,
+    speed: f32,
+    repeat_mode: RepeatMode,
+) -> bool {
+    match repeat_mode.end(duration) {
+        Some(_) if speed < 0.0 => elapsed < 0.0,
+        Some(end) => elapsed >= end,
+        None => false,
+    }
+}
+
+/// Times elapsed at which the time `clip_time` of a clip of `duration` is
+/// played, between `from` and `to`. The first time of the reverse part of a
+/// ping-pong is skipped, as the forward part ends at the same time.
+pub(crate) fn elapsed_times(
+    clip_time: f32,
+    from: f32,
+    to: f32,
+    duration: f32,
+    repeat_mode: RepeatMode,
+) -> Vec<(i64, f32)> {
+    if duration <= 0.0 {
+        return vec![(0, 0.0)];
+    }
+    let ping_pong = matches!(
+        repeat_mode,
+        RepeatMode::PingPong | RepeatMode::PingPongCount(_)
+    );
+    // Parts of the playback in which the clip is played in one direction
+    let part = |elapsed: f32| (elapsed / duration).floor() as i64;
+    let (mut first, mut last) = (part(from.min(to)), part(from.max(to)));
+    if let Some(end) = repeat_mode.end(duration) {
+        first = first.max(0);
+        last = last.min((end / duration).round() as i64 - 1);
+    }
+    (first..=last)
+        .filter_map(|part| {
+            let reverse = ping_pong && part.rem_euclid(2) == 1;
+            let start = part as f32 * duration;
+            if reverse && clip_time >= duration {
+                None
+            } else if reverse {
+                Some((part, start + duration - clip_time))
+            } else if ping_pong && part > 0 && clip_time <= 0.0 {
+                None
+            } else {
+                Some((part, start + clip_time))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_time_follows_repeat_mode() {
+        let time = |elapsed, mode| clip_elapsed(elapsed, 1.0, mode);
+        assert_eq!(time(1.5, RepeatMode::Once), 1.0);
+        assert_eq!(time(-0.5, RepeatMode::Once), 0.0);
+        assert_eq!(time(1.25, RepeatMode::Loop), 0.25);
+        assert_eq!(time(-0.25, RepeatMode::Loop), 0.75);
+        assert_eq!(time(1.25, RepeatMode::LoopCount(2)), 0.25);
+        assert_eq!(time(2.25, RepeatMode::LoopCount(2)), 1.0);
+        assert_eq!(time(1.25, RepeatMode::PingPong), 0.75);
+        assert_eq!(time(2.25, RepeatMode::PingPong), 0.25);
+        assert_eq!(time(3.0, RepeatMode::PingPongCount(1)), 0.0);
+    }
+
+    #[test]
+    fn loops_are_counted_both_ways() {
+        let loops = |from, to, mode| crossed_loops(from, to, 1.0, mode);
+        assert_eq!(loops(0.0, 0.5, RepeatMode::Loop), 0);
+        assert_eq!(loops(0.5, 1.0, RepeatMode::Loop), 1);
+        assert_eq!(loops(0.5, 3.5, RepeatMode::Loop), 3);
+        assert_eq!(loops(0.5, -0.5, RepeatMode::Loop), 1);
+        // The end of the last loop finishes the clip instead
+        assert_eq!(loops(0.5, 3.5, RepeatMode::LoopCount(2)), 1);
+        assert_eq!(loops(0.5, 3.5, RepeatMode::PingPong), 1);
+        assert_eq!(loops(0.5, 3.5, RepeatMode::Once), 0);
+    }
+
+    #[test]
+    fn clips_finish_at_their_end() {
+        let finished = |elapsed, speed, mode| is_finished(elapsed, 1.0, speed, mode);
+        assert!(!finished(0.5, 1.0, RepeatMode::Once));
+        assert!(finished(1.0, 1.0, RepeatMode::Once));
+        assert!(!finished(0.0, -1.0, RepeatMode::Once));
+        assert!(finished(-0.1, -1.0, RepeatMode::Once));
+        assert!(!finished(1.5, 1.0, RepeatMode::LoopCount(2)));
+        assert!(finished(2.0, 1.0, RepeatMode::LoopCount(2)));
+        assert!(!finished(3.0, 1.0, RepeatMode::PingPongCount(2)));
+        assert!(finished(4.0, 1.0, RepeatMode::PingPongCount(2)));
+        assert!(!finished(100.0, 1.0, RepeatMode::PingPong));
+    }
+}
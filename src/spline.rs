@@ -0,0 +1,401 @@
+use crate::{
+    lerp::{Animatable, Lerp},
+    Keyframe, KeyframeChannels, KeyframeVariableCurve,
+};
+
+/// How the values between two keyframes of a [`KeyframeVariableCurve`] are
+/// computed.
+#[derive(Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(bound(
+        serialize = "Keyframe<T>: serde::Serialize",
+        deserialize = "Keyframe<T>: serde::Deserialize<'de>"
+    ))
+)]
+pub enum KeyframeInterpolation<T> {
+    /// Straight line between the two keyframes.
+    #[default]
+    Linear,
+    /// Cubic Bézier curve, with one [`KeyframeHandles`] per keyframe.
+    CubicBezier(Vec<KeyframeHandles<T>>),
+}
+
+/// Bézier control handles of a keyframe, as absolute values like the handles
+/// of Blender or After Effects.
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(bound(
+        serialize = "Keyframe<T>: serde::Serialize",
+        deserialize = "Keyframe<T>: serde::Deserialize<'de>"
+    ))
+)]
+pub struct KeyframeHandles<T> {
+    /// Control point used by the segment ending at this keyframe.
+    pub in_handle: Keyframe<T>,
+    /// Control point used by the segment starting at this keyframe.
+    pub out_handle: Keyframe<T>,
+}
+
+/// Parameters of a Kochanek–Bartels spline, each in `[-1, 1]`. The default
+/// parameters give a Catmull-Rom spline.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KochanekBartels {
+    /// How sharply the curve bends at the keyframes, `1.0` gives corners.
+    pub tension: f32,
+    /// How abrupt the change of direction is at the keyframes.
+    pub continuity: f32,
+    /// Whether the curve overshoots (`> 0`) or undershoots (`< 0`) the
+    /// keyframes.
+    pub bias: f32,
+}
+
+/// Hermite tangents of a keyframe, as a rate of change per second.
+pub struct KeyframeTangents<T> {
+    /// Tangent of the segment ending at this keyframe.
+    pub in_tangent: Keyframe<T>,
+    /// Tangent of the segment starting at this keyframe.
+    pub out_tangent: Keyframe<T>,
+}
+
+impl<T> KeyframeInterpolation<T> {
+    /// Interpolate between the keyframes `from` and `to`, the keyframes at
+    /// index `segment` and `segment + 1`.
+    pub(crate) fn interpolate(
+        &self,
+        segment: usize,
+        from: &Keyframe<T>,
+        to: &Keyframe<T>,
+        scalar: f32,
+        target: &T,
+        channels: &KeyframeChannels<T>,
+    ) -> Keyframe<T>
+    where
+        Keyframe<T>: Lerp<T>,
+    {
+        let handles = match self {
+            KeyframeInterpolation::CubicBezier(handles) => {
+                handles.get(segment).zip(handles.get(segment + 1))
+            }
+            KeyframeInterpolation::Linear => None,
+        };
+        match handles {
+            // De Casteljau's algorithm, so that only `Lerp` is needed to
+            // evaluate the curve
+            Some((start, end)) => {
+                let a = from.lerp(&start.out_handle, scalar, target, channels);
+                let b = start
+                    .out_handle
+                    .lerp(&end.in_handle, scalar, target, channels);
+                let c = end.in_handle.lerp(to, scalar, target, channels);
+                let d = a.lerp(&b, scalar, target, channels);
+                let e = b.lerp(&c, scalar, target, channels);
+                d.lerp(&e, scalar, target, channels)
+            }
+            None => from.lerp(to, scalar, target, channels),
+        }
+    }
+}
+
+impl<T> KeyframeVariableCurve<T>
+where
+    T: Clone,
+    Keyframe<T>: Lerp<T> + Animatable,
+{
+    /// Create a cubic Hermite curve going through the `keyframes`, leaving
+    /// and reaching each keyframe with the given `tangents`.
+    ///
+    /// `keyframe_timestamps`, `keyframes` and `tangents` should have the same
+    /// length.
+    pub fn hermite(
+        keyframe_timestamps: Vec<f32>,
+        keyframes: Vec<Keyframe<T>>,
+        tangents: Vec<KeyframeTangents<T>>,
+    ) -> Self {
+        let handles = keyframes
+            .iter()
+            .zip(tangents.iter())
+            .enumerate()
+            .map(|(i, (keyframe, tangents))| {
+                // A Hermite segment is a Bézier curve with handles at a third
+                // of the segment duration along the tangents
+                let before = i
+                    .checked_sub(1)
+                    .and_then(|previous| keyframe_timestamps.get(previous))
+                    .map_or(0.0, |previous| keyframe_timestamps[i] - previous);
+                let after = keyframe_timestamps
+                    .get(i + 1)
+                    .map_or(0.0, |next| next - keyframe_timestamps[i]);
+                KeyframeHandles {
+                    in_handle: keyframe.add(&tangents.in_tangent.scale(-before / 3.0)),
+                    out_handle: keyframe.add(&tangents.out_tangent.scale(after / 3.0)),
+                }
+            })
+            .collect();
+        Self {
+            keyframe_timestamps,
+            keyframes,
+            interpolation: KeyframeInterpolation::CubicBezier(handles),
+            ..Default::default()
+        }
+    }
+
+    /// Create a Catmull-Rom spline going through all the `keyframes`.
+    ///
+    /// When `closed`, the first and last keyframes are expected to be equal
+    /// and the curve is smooth when looping.
+    pub fn catmull_rom(
+        keyframe_timestamps: Vec<f32>,
+        keyframes: Vec<Keyframe<T>>,
+        closed: bool,
+    ) -> Self {
+        Self::kochanek_bartels(
+            keyframe_timestamps,
+            keyframes,
+            KochanekBartels::default(),
+            closed,
+        )
+    }
+
+    /// Create a Kochanek–Bartels spline going through all the `keyframes`.
+    ///
+    /// When `closed`, the first and last keyframes are expected to be equal
+    /// and the curve is smooth when looping.
+    pub fn kochanek_bartels(
+        keyframe_timestamps: Vec<f32>,
+        keyframes: Vec<Keyframe<T>>,
+        parameters: KochanekBartels,
+        closed: bool,
+    ) -> Self {
+        let KochanekBartels {
+            tension,
+            continuity,
+            bias,
+        } = parameters;
+        let tangents = (0..keyframes.len())
+            .map(|i| {
+                let (before, after) =
+                    neighbour_velocities(&keyframe_timestamps, &keyframes, i, closed);
+                let weighted = |before_weight: f32, after_weight: f32| {
+                    before
+                        .scale((1.0 - tension) * before_weight / 2.0)
+                        .add(&after.scale((1.0 - tension) * after_weight / 2.0))
+                };
+                KeyframeTangents {
+                    in_tangent: weighted(
+                        (1.0 - continuity) * (1.0 + bias),
+                        (1.0 + continuity) * (1.0 - bias),
+                    ),
+                    out_tangent: weighted(
+                        (1.0 + continuity) * (1.0 + bias),
+                        (1.0 - continuity) * (1.0 - bias),
+                    ),
+                }
+            })
+            .collect();
+        Self::hermite(keyframe_timestamps, keyframes, tangents)
+    }
+
+    /// Create a centripetal Catmull-Rom spline going through all the
+    /// `keyframes`. Unlike [`Self::catmull_rom`], it never forms loops or
+    /// cusps when keyframes are unevenly spaced.
+    ///
+    /// When `closed`, the first and last keyframes are expected to be equal
+    /// and the curve is smooth when looping.
+    pub fn centripetal_catmull_rom(
+        keyframe_timestamps: Vec<f32>,
+        keyframes: Vec<Keyframe<T>>,
+        closed: bool,
+    ) -> Self {
+        let tangents = (0..keyframes.len())
+            .map(|i| {
+                match neighbours(keyframes.len(), i, closed) {
+                    (Some(previous), Some(next)) => {
+                        // Knot intervals are the square root of the distance between the keyframes
+                        let (p0, p1, p2) = (&keyframes[previous], &keyframes[i], &keyframes[next]);
+                        let d0 = p0.distance(p1).sqrt().max(f32::EPSILON);
+                        let d1 = p1.distance(p2).sqrt().max(f32::EPSILON);
+                        let derivative = p1
+                            .add(&p0.scale(-1.0))
+                            .scale(1.0 / d0)
+                            .add(&p2.add(&p0.scale(-1.0)).scale(-1.0 / (d0 + d1)))
+                            .add(&p2.add(&p1.scale(-1.0)).scale(1.0 / d1));
+                        // Convert the derivative along the knots to a rate per second
+                        let (before, after) = segment_durations(&keyframe_timestamps, i, closed);
+                        KeyframeTangents {
+                            in_tangent: derivative.scale(d0 / before),
+                            out_tangent: derivative.scale(d1 / after),
+                        }
+                    }
+                    // The ends of an open curve behave like a Catmull-Rom spline
+                    _ => {
+                        let (before, after) =
+                            neighbour_velocities(&keyframe_timestamps, &keyframes, i, closed);
+                        let tangent = before.add(&after).scale(0.5);
+                        KeyframeTangents {
+                            in_tangent: tangent.clone(),
+                            out_tangent: tangent,
+                        }
+                    }
+                }
+            })
+            .collect();
+        Self::hermite(keyframe_timestamps, keyframes, tangents)
+    }
+}
+
+/// Indices of the keyframes before and after the keyframe `i`, wrapping
+/// around when the curve is `closed`.
+fn neighbours(len: usize, i: usize, closed: bool) -> (Option<usize>, Option<usize>) {
+    let closed = closed && len > 2;
+    let previous = match i {
+        0 if closed => Some(len - 2),
+        0 => None,
+        _ => Some(i - 1),
+    };
+    let next = match i + 1 {
+        n if n < len => Some(n),
+        _ if closed => Some(1),
+        _ => None,
+    };
+    (previous, next)
+}
+
+/// Durations of the segments ending and starting at the keyframe `i`.
+fn segment_durations(keyframe_timestamps: &[f32], i: usize, closed: bool) -> (f32, f32) {
+    let len = keyframe_timestamps.len();
+    let (previous, next) = neighbours(len, i, closed);
+    let before = match previous {
+        Some(_) if i == 0 => keyframe_timestamps[len - 1] - keyframe_timestamps[len - 2],
+        Some(previous) => keyframe_timestamps[i] - keyframe_timestamps[previous],
+        None => 0.0,
+    };
+    let after = match next {
+        Some(_) if i == len - 1 => keyframe_timestamps[1] - keyframe_timestamps[0],
+        Some(next) => keyframe_timestamps[next] - keyframe_timestamps[i],
+        None => 0.0,
+    };
+    (before.max(f32::EPSILON), after.max(f32::EPSILON))
+}
+
+/// Rate of change per second of the segments ending and starting at the
+/// keyframe `i`. At the ends of an open curve, the missing side mirrors the
+/// other one.
+fn neighbour_velocities<T>(
+    keyframe_timestamps: &[f32],
+    keyframes: &[Keyframe<T>],
+    i: usize,
+    closed: bool,
+) -> (Keyframe<T>, Keyframe<T>)
+where
+    T: Clone,
+    Keyframe<T>: Animatable,
+{
+    let (previous, next) = neighbours(keyframes.len(), i, closed);
+    let (before, after) = segment_durations(keyframe_timestamps, i, closed);
+    let keyframe = &keyframes[i];
+    let velocity_before = previous.map(|previous| {
+        keyframe
+            .add(&keyframes[previous].scale(-1.0))
+            .scale(1.0 / before)
+    });
+    let velocity_after = next.map(|next| {
+        keyframes[next]
+            .add(&keyframe.scale(-1.0))
+            .scale(1.0 / after)
+    });
+    match (velocity_before, velocity_after) {
+        (Some(before), Some(after)) => (before, after),
+        (Some(before), None) => (before.clone(), before),
+        (None, Some(after)) => (after.clone(), after),
+        (None, None) => (keyframe.scale(0.0), keyframe.scale(0.0)),
+    }
+}
+
+impl<T> Animatable for Keyframe<T>
+where
+    T: Animatable,
+{
+    fn add(&self, other: &Self) -> Self {
+        Keyframe(self.0.add(&other.0))
+    }
+
+    fn scale(&self, scalar: f32) -> Self {
+        Keyframe(self.0.scale(scalar))
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.0.distance(&other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+
+    fn square() -> (Vec<f32>, Vec<Keyframe<Transform>>) {
+        (
+            vec![0.0, 1.0, 2.0, 3.0, 4.0],
+            Keyframe::translation(vec![
+                Vec3::new(1.0, 0.0, 1.0),
+                Vec3::new(-1.0, 0.0, 1.0),
+                Vec3::new(-1.0, 0.0, -1.0),
+                Vec3::new(1.0, 0.0, -1.0),
+                Vec3::new(1.0, 0.0, 1.0),
+            ]),
+        )
+    }
+
+    fn translation_at(curve: &KeyframeVariableCurve<Transform>, elapsed: f32) -> Vec3 {
+        curve
+            .sample(elapsed, &Transform::default())
+            .unwrap()
+            .0
+            .translation
+    }
+
+    #[test]
+    fn splines_go_through_keyframes() {
+        let (timestamps, keyframes) = square();
+        let expected: Vec<_> = keyframes.iter().map(|k| k.0.translation).collect();
+        for curve in [
+            KeyframeVariableCurve::catmull_rom(timestamps.clone(), keyframes.clone(), false),
+            KeyframeVariableCurve::centripetal_catmull_rom(
+                timestamps.clone(),
+                keyframes.clone(),
+                true,
+            ),
+            KeyframeVariableCurve::kochanek_bartels(
+                timestamps.clone(),
+                keyframes.clone(),
+                KochanekBartels {
+                    tension: 0.5,
+                    continuity: -0.5,
+                    bias: 0.2,
+                },
+                true,
+            ),
+        ] {
+            for (timestamp, expected) in timestamps.iter().zip(&expected) {
+                assert!(translation_at(&curve, *timestamp).distance(*expected) < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn closed_splines_loop_smoothly() {
+        let (timestamps, keyframes) = square();
+        let curve = KeyframeVariableCurve::catmull_rom(timestamps, keyframes, true);
+
+        let step = 0.01;
+        let leaving = translation_at(&curve, step) - translation_at(&curve, 0.0);
+        let arriving = translation_at(&curve, 4.0) - translation_at(&curve, 4.0 - step);
+        assert!(leaving.distance(arriving) < 1e-3);
+        // The corners of the square are rounded
+        assert!(translation_at(&curve, 0.5).z > 1.0);
+    }
+}
@@ -0,0 +1,427 @@
+use bevy::{asset::Asset, prelude::*, utils::HashMap};
+
+use crate::{
+    lerp::Lerp, repeat::RepeatMode, Keyframe, KeyframeAnimationClip, KeyframeAnimationPlayer,
+};
+
+/// Label of the [`animation_state_machine`] systems added by the plugin.
+/// Systems setting parameters should run before it to be picked up in the
+/// same frame.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, SystemLabel)]
+pub struct AnimationStateMachineSystem;
+
+/// Clip played while an [`AnimationStateMachine`] is in a state.
+#[derive(Debug)]
+pub struct AnimationState<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    /// Clip played in the state.
+    pub animation_clip: Handle<KeyframeAnimationClip<T>>,
+    /// How the clip repeats.
+    pub repeat_mode: RepeatMode,
+    /// Speed of the clip playback.
+    pub speed: f32,
+}
+
+impl<T> AnimationState<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    pub fn new(animation_clip: Handle<KeyframeAnimationClip<T>>) -> Self {
+        Self {
+            animation_clip,
+            repeat_mode: RepeatMode::Once,
+            speed: 1.0,
+        }
+    }
+}
+
+/// Value of a parameter of an [`AnimationStateMachine`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnimationParameter {
+    Bool(bool),
+    /// Boolean reset once a transition using it is taken.
+    Trigger(bool),
+    Float(f32),
+}
+
+/// Condition on the parameters for a transition to be taken. A missing
+/// parameter, or one of another kind, fails the condition.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnimationCondition {
+    /// The bool parameter has the given value.
+    Bool(String, bool),
+    /// The trigger parameter is set.
+    Trigger(String),
+    /// The float parameter is greater than the given value.
+    Greater(String, f32),
+    /// The float parameter is less than the given value.
+    Less(String, f32),
+}
+
+impl AnimationCondition {
+    fn is_met(&self, parameters: &HashMap<String, AnimationParameter>) -> bool {
+        match self {
+            AnimationCondition::Bool(name, value) => {
+                parameters.get(name) == Some(&AnimationParameter::Bool(*value))
+            }
+            AnimationCondition::Trigger(name) => {
+                parameters.get(name) == Some(&AnimationParameter::Trigger(true))
+            }
+            AnimationCondition::Greater(name, value) => {
+                matches!(parameters.get(name), Some(AnimationParameter::Float(float)) if float > value)
+            }
+            AnimationCondition::Less(name, value) => {
+                matches!(parameters.get(name), Some(AnimationParameter::Float(float)) if float < value)
+            }
+        }
+    }
+}
+
+/// Transition between two states of an [`AnimationStateMachine`], taken when
+/// all of its conditions are met.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationStateTransition {
+    /// State the transition starts from, or `None` to start from any other
+    /// state.
+    pub from: Option<String>,
+    /// State the transition goes to.
+    pub to: String,
+    /// Conditions on the parameters, all of which must be met.
+    pub conditions: Vec<AnimationCondition>,
+    /// Fraction of the clip of the `from` state that must have been played,
+    /// e.g. `1.0` to wait for its end. Above `1.0` for a repeating clip waits
+    /// for the following loops.
+    pub exit_time: Option<f32>,
+    /// Duration of the crossfade between the clips, in seconds. The clips
+    /// are switched at once if it's zero.
+    pub duration: f32,
+}
+
+impl AnimationStateTransition {
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: Some(from.into()),
+            to: to.into(),
+            conditions: Vec::new(),
+            exit_time: None,
+            duration: 0.0,
+        }
+    }
+
+    /// Create a transition from any other state.
+    pub fn from_any(to: impl Into<String>) -> Self {
+        Self {
+            from: None,
+            ..Self::new("", to)
+        }
+    }
+
+    /// Add a condition to the transition
+    pub fn with_condition(mut self, condition: AnimationCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Set the fraction of the clip that must have been played
+    pub fn with_exit_time(mut self, exit_time: f32) -> Self {
+        self.exit_time = Some(exit_time);
+        self
+    }
+
+    /// Set the duration of the crossfade
+    pub fn with_duration(mut self, duration: f32) -> Self {
+        self.duration = duration;
+        self
+    }
+}
+
+/// Drives the [`KeyframeAnimationPlayer`] of its entity from named states,
+/// switching between them through transitions conditioned on parameters the
+/// game sets. The player is added by [`animation_state_machine`] if the
+/// entity doesn't have one yet.
+#[derive(Component, Debug)]
+pub struct AnimationStateMachine<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    states: HashMap<String, AnimationState<T>>,
+    transitions: Vec<AnimationStateTransition>,
+    parameters: HashMap<String, AnimationParameter>,
+    current_state: String,
+    previous_state: Option<String>,
+    /// Whether the player plays the clip of the current state
+    entered: bool,
+}
+
+impl<T> AnimationStateMachine<T>
+where
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    /// Create a state machine starting in the given state
+    pub fn new(initial_state: impl Into<String>, state: AnimationState<T>) -> Self {
+        let initial_state = initial_state.into();
+        let mut states = HashMap::default();
+        states.insert(initial_state.clone(), state);
+        Self {
+            states,
+            transitions: Vec::new(),
+            parameters: HashMap::default(),
+            current_state: initial_state,
+            previous_state: None,
+            entered: false,
+        }
+    }
+
+    /// Add a state, replacing the one with the same name
+    pub fn add_state(&mut self, name: impl Into<String>, state: AnimationState<T>) -> &mut Self {
+        self.states.insert(name.into(), state);
+        self
+    }
+
+    /// Add a transition. Transitions are checked in the order they're added,
+    /// and the ones to a missing state are ignored.
+    pub fn add_transition(&mut self, transition: AnimationStateTransition) -> &mut Self {
+        self.transitions.push(transition);
+        self
+    }
+
+    /// Set a bool parameter
+    pub fn set_bool(&mut self, name: impl Into<String>, value: bool) -> &mut Self {
+        self.parameters
+            .insert(name.into(), AnimationParameter::Bool(value));
+        self
+    }
+
+    /// Set a float parameter
+    pub fn set_float(&mut self, name: impl Into<String>, value: f32) -> &mut Self {
+        self.parameters
+            .insert(name.into(), AnimationParameter::Float(value));
+        self
+    }
+
+    /// Set a trigger, until a transition using it is taken
+    pub fn set_trigger(&mut self, name: impl Into<String>) -> &mut Self {
+        self.parameters
+            .insert(name.into(), AnimationParameter::Trigger(true));
+        self
+    }
+
+    /// Reset a trigger that wasn't used by any transition
+    pub fn reset_trigger(&mut self, name: impl Into<String>) -> &mut Self {
+        self.parameters
+            .insert(name.into(), AnimationParameter::Trigger(false));
+        self
+    }
+
+    /// Value of a parameter
+    pub fn parameter(&self, name: &str) -> Option<AnimationParameter> {
+        self.parameters.get(name).copied()
+    }
+
+    /// All the parameters, by name
+    pub fn parameters(&self) -> &HashMap<String, AnimationParameter> {
+        &self.parameters
+    }
+
+    /// All the states, by name
+    pub fn states(&self) -> &HashMap<String, AnimationState<T>> {
+        &self.states
+    }
+
+    /// All the transitions, in the order they're checked
+    pub fn transitions(&self) -> &[AnimationStateTransition] {
+        &self.transitions
+    }
+
+    /// Name of the current state
+    pub fn current_state(&self) -> &str {
+        &self.current_state
+    }
+
+    /// Name of the state before the current one, if there was one
+    pub fn previous_state(&self) -> Option<&str> {
+        self.previous_state.as_deref()
+    }
+
+    /// First transition that can be taken from the current state, given the
+    /// fraction of its clip that was played
+    fn next_transition(&self, played: Option<f32>) -> Option<&AnimationStateTransition> {
+        self.transitions.iter().find(|transition| {
+            let from = match &transition.from {
+                Some(from) => *from == self.current_state,
+                None => transition.to != self.current_state,
+            };
+            from && self.states.contains_key(&transition.to)
+                && transition
+                    .conditions
+                    .iter()
+                    .all(|condition| condition.is_met(&self.parameters))
+                && match (transition.exit_time, played) {
+                    (Some(exit_time), Some(played)) => played >= exit_time,
+                    // The clip isn't loaded yet
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                }
+        })
+    }
+
+    /// Configure `player` to play the current state
+    fn play_current(&self, player: &mut KeyframeAnimationPlayer<T>, duration: f32) {
+        let state = &self.states[&self.current_state];
+        if duration > 0.0 {
+            player.play_with_transition(state.animation_clip.clone(), duration);
+        } else {
+            player.play(state.animation_clip.clone());
+        }
+        player
+            .set_repeat_mode(state.repeat_mode)
+            .set_speed(state.speed);
+    }
+}
+
+/// System that takes the transitions of the [`AnimationStateMachine`]s, and
+/// plays the clip of their current state with the
+/// [`KeyframeAnimationPlayer`] of their entity.
+#[allow(clippy::type_complexity)]
+pub fn animation_state_machine<T>(
+    mut commands: Commands,
+    animations: Res<Assets<KeyframeAnimationClip<T>>>,
+    mut state_machines: Query<(
+        Entity,
+        &mut AnimationStateMachine<T>,
+        Option<&mut KeyframeAnimationPlayer<T>>,
+    )>,
+) where
+    T: Component,
+    Keyframe<T>: Lerp<T>,
+    KeyframeAnimationClip<T>: Asset,
+{
+    for (entity, mut state_machine, player) in state_machines.iter_mut() {
+        let mut player = match player {
+            Some(player) => player,
+            None => {
+                let state = &state_machine.states[&state_machine.current_state];
+                let mut player = KeyframeAnimationPlayer::new(state.animation_clip.clone());
+                player
+                    .set_repeat_mode(state.repeat_mode)
+                    .set_speed(state.speed);
+                commands.entity(entity).insert(player);
+                state_machine.entered = true;
+                continue;
+            }
+        };
+        if !state_machine.entered {
+            state_machine.play_current(&mut player, 0.0);
+            state_machine.entered = true;
+            continue;
+        }
+        // An empty clip is done playing as soon as it starts
+        let played = animations.get(player.animation_clip()).map(|clip| {
+            if clip.duration() > 0.0 {
+                player.elapsed().abs() / clip.duration()
+            } else {
+                f32::INFINITY
+            }
+        });
+        let transition = match state_machine.next_transition(played) {
+            Some(transition) => transition.clone(),
+            None => continue,
+        };
+        // Consume the triggers of the transition
+        for condition in &transition.conditions {
+            if let AnimationCondition::Trigger(name) = condition {
+                state_machine.reset_trigger(name.clone());
+            }
+        }
+        debug!(
+            "{:?} went from animation state {} to {}",
+            entity, state_machine.current_state, transition.to
+        );
+        let previous_state = std::mem::replace(&mut state_machine.current_state, transition.to);
+        state_machine.previous_state = Some(previous_state);
+        state_machine.play_current(&mut player, transition.duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::AssetPlugin, core::CorePlugin, ecs::schedule::SystemStage};
+
+    use super::*;
+
+    fn run(world: &mut World) {
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(animation_state_machine::<Transform>);
+        stage.run(world);
+    }
+
+    #[test]
+    fn transitions_follow_parameters() {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<KeyframeAnimationClip<Transform>>();
+        let mut world = std::mem::take(&mut app.world);
+        let mut clips = world.resource_mut::<Assets<KeyframeAnimationClip<Transform>>>();
+        let [idle, run_clip, jump]: [Handle<KeyframeAnimationClip<Transform>>; 3] =
+            [(); 3].map(|_| clips.add(KeyframeAnimationClip::default()));
+
+        let mut state_machine = AnimationStateMachine::new("idle", AnimationState::new(idle));
+        state_machine
+            .add_state("run", AnimationState::new(run_clip.clone()))
+            .add_state("jump", AnimationState::new(jump.clone()))
+            .add_transition(
+                AnimationStateTransition::new("idle", "run")
+                    .with_condition(AnimationCondition::Greater("speed".to_string(), 0.5)),
+            )
+            .add_transition(
+                AnimationStateTransition::from_any("jump")
+                    .with_condition(AnimationCondition::Trigger("jump".to_string()))
+                    .with_duration(0.2),
+            );
+        let entity = world.spawn().insert(state_machine).id();
+
+        // The player is added in the initial state
+        run(&mut world);
+        assert!(world
+            .get::<KeyframeAnimationPlayer<Transform>>(entity)
+            .is_some());
+
+        world
+            .get_mut::<AnimationStateMachine<Transform>>(entity)
+            .unwrap()
+            .set_float("speed", 1.0);
+        run(&mut world);
+        let state_machine = world
+            .get::<AnimationStateMachine<Transform>>(entity)
+            .unwrap();
+        assert_eq!(state_machine.current_state(), "run");
+        assert_eq!(state_machine.previous_state(), Some("idle"));
+        let player = world
+            .get::<KeyframeAnimationPlayer<Transform>>(entity)
+            .unwrap();
+        assert_eq!(player.animation_clip(), &run_clip);
+
+        // The trigger is consumed by the transition
+        world
+            .get_mut::<AnimationStateMachine<Transform>>(entity)
+            .unwrap()
+            .set_trigger("jump");
+        run(&mut world);
+        run(&mut world);
+        let state_machine = world
+            .get::<AnimationStateMachine<Transform>>(entity)
+            .unwrap();
+        assert_eq!(state_machine.current_state(), "jump");
+        assert_eq!(
+            state_machine.parameter("jump"),
+            Some(AnimationParameter::Trigger(false))
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::ops::Deref;
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::KeyframeEntityPath;
+
+/// Entities whose `Children` or `Name` changed, invalidating the caches.
+pub(crate) type HierarchyChanges<'w, 's> =
+    Query<'w, 's, (), Or<(Changed<Children>, Changed<Name>)>>;
+
+/// Entities resolved from the paths of a player, kept until the hierarchy
+/// changes.
+#[derive(Default)]
+pub(crate) struct KeyframeTargetCache {
+    bindings: HashMap<KeyframeEntityPath, Option<Entity>>,
+    /// Paths reported as unresolved, until they resolve again
+    warned: HashSet<KeyframeEntityPath>,
+    /// Other problems of the player, reported once
+    warned_messages: HashSet<String>,
+}
+
+impl KeyframeTargetCache {
+    /// Forget the resolved entities, after a `Children` or `Name` changed.
+    pub(crate) fn clear(&mut self) {
+        self.bindings.clear();
+    }
+
+    /// Entity at `path` from the animation `root`, resolved the first time
+    /// it's asked for. An unresolved path is reported once.
+    pub(crate) fn get(
+        &mut self,
+        root: Entity,
+        path: &KeyframeEntityPath,
+        children: &Query<&Children>,
+        names: &Query<&Name>,
+    ) -> Option<Entity> {
+        if let Some(target) = self.bindings.get(path) {
+            return *target;
+        }
+        let target = match find_target(root, path, children, names) {
+            Ok(target) => {
+                self.warned.remove(path);
+                Some(target)
+            }
+            Err(part) => {
+                if self.warned.insert(path.clone()) {
+                    warn!("Entity not found for path {:?} on part {:?}", path, part);
+                }
+                None
+            }
+        };
+        self.bindings.insert(path.clone(), target);
+        target
+    }
+
+    /// Log `message` as a warning, unless it was already logged.
+    pub(crate) fn warn_once(&mut self, message: String) {
+        if !self.warned_messages.contains(&message) {
+            warn!("{}", message);
+            self.warned_messages.insert(message);
+        }
+    }
+}
+
+/// Find the entity at `path` from the animation `root`, or the first part of
+/// the path that isn't found.
+fn find_target<'a>(
+    root: Entity,
+    path: &'a KeyframeEntityPath,
+    children: &Query<&Children>,
+    names: &Query<&Name>,
+) -> Result<Entity, &'a Name> {
+    let mut current_entity = root;
+    // Ignore the first name, it is the root node which we already have
+    for part in path.parts.iter().skip(1) {
+        let mut found = false;
+        if let Ok(children) = children.get(current_entity) {
+            for child in children.deref() {
+                if let Ok(name) = names.get(*child) {
+                    if name == part {
+                        // Found a children with the right name, continue to the
+                        // next part
+                        current_entity = *child;
+                        found = true;
+                        break;
+                    }
+                }
+            }
+        }
+        if !found {
+            return Err(part);
+        }
+    }
+    Ok(current_entity)
+}
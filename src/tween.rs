@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+
+use crate::{ease::Ease, event::TweenFinished, lerp::Lerp, Keyframe, KeyframeChannels};
+
+/// One-off animation of the `T` of its entity, from its current value to a
+/// target, without building a clip. The tween removes itself once finished,
+/// sending a [`TweenFinished`] event.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use keyframe_animate::prelude::*;
+/// fn nudge(mut commands: Commands, query: Query<Entity, With<Sprite>>) {
+///     for entity in query.iter() {
+///         commands.entity(entity).insert(
+///             Tween::to(Transform::from_xyz(0.0, 10.0, 0.0))
+///                 .duration(0.3)
+///                 .ease(Ease::BackOut),
+///         );
+///     }
+/// }
+/// ```
+#[derive(Component)]
+pub struct Tween<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    target: Keyframe<T>,
+    /// Value of the component when the tween started
+    start: Option<Keyframe<T>>,
+    channels: KeyframeChannels<T>,
+    duration: f32,
+    ease: Ease,
+    elapsed: f32,
+    user_data: u64,
+}
+
+impl<T> Tween<T>
+where
+    Keyframe<T>: Lerp<T>,
+{
+    /// Create a tween to `target` over one second, animating every attribute
+    /// of `T` linearly
+    pub fn to(target: T) -> Self {
+        Self {
+            target: Keyframe(target),
+            start: None,
+            channels: Default::default(),
+            duration: 1.0,
+            ease: Ease::Linear,
+            elapsed: 0.0,
+            user_data: 0,
+        }
+    }
+
+    /// Set the duration of the tween, in seconds
+    pub fn duration(mut self, duration: f32) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Set the easing of the tween
+    pub fn ease(mut self, ease: Ease) -> Self {
+        self.ease = ease;
+        self
+    }
+
+    /// Set the attributes of `T` animated by the tween, the others keep
+    /// their current value
+    pub fn channel(mut self, channels: impl Into<KeyframeChannels<T>>) -> Self {
+        self.channels = channels.into();
+        self
+    }
+
+    /// Set the data sent with the [`TweenFinished`] event, to tell tweens
+    /// apart
+    pub fn user_data(mut self, user_data: u64) -> Self {
+        self.user_data = user_data;
+        self
+    }
+
+    /// Value the tween goes to
+    pub fn target(&self) -> &T {
+        &self.target.0
+    }
+
+    /// Progress of the tween, between `0.0` and `1.0`
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.elapsed / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// System that plays the [`Tween`]s of `T`, removing the finished ones.
+pub fn tween_player<T>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tweens: Query<(Entity, &mut Tween<T>, &mut T)>,
+    mut finished_events: EventWriter<TweenFinished>,
+) where
+    T: Component + Clone,
+    Keyframe<T>: Lerp<T>,
+{
+    for (entity, mut tween, mut object) in tweens.iter_mut() {
+        let tween = &mut *tween;
+        tween.elapsed += time.delta_seconds();
+        let progress = tween.progress();
+        let start = tween.start.get_or_insert_with(|| Keyframe(object.clone()));
+        let keyframe = start.lerp(
+            &tween.target,
+            tween.ease.apply(progress),
+            &*object,
+            &tween.channels,
+        );
+        *object = keyframe.0;
+        if progress >= 1.0 {
+            commands.entity(entity).remove::<Tween<T>>();
+            finished_events.send(TweenFinished {
+                entity,
+                user_data: tween.user_data,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{event::Events, schedule::SystemStage};
+
+    use super::*;
+    use crate::channels::TransformChannels;
+
+    #[test]
+    fn tweens_remove_themselves_once_finished() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Events<TweenFinished>>();
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(tween_player::<Transform>);
+
+        let mut tween = Tween::to(Transform::from_xyz(2.0, 0.0, 0.0).with_scale(Vec3::splat(3.0)))
+            .channel(TransformChannels::TRANSLATION)
+            .user_data(7);
+        // Halfway through the tween
+        tween.elapsed = 0.5;
+        let entity = world
+            .spawn()
+            .insert_bundle((Transform::from_xyz(0.0, 1.0, 0.0), tween))
+            .id();
+        let mut finished = world.resource::<Events<TweenFinished>>().get_reader();
+
+        stage.run(&mut world);
+        let transform = world.get::<Transform>(entity).unwrap();
+        assert_eq!(transform.translation, Vec3::new(1.0, 0.5, 0.0));
+        assert_eq!(transform.scale, Vec3::ONE);
+        assert_eq!(finished.iter(world.resource()).count(), 0);
+
+        world.get_mut::<Tween<Transform>>(entity).unwrap().elapsed = 1.0;
+        stage.run(&mut world);
+        assert_eq!(
+            world.get::<Transform>(entity).unwrap().translation,
+            Vec3::X * 2.0
+        );
+        assert!(world.get::<Tween<Transform>>(entity).is_none());
+        let events: Vec<_> = finished.iter(world.resource()).collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entity, entity);
+        assert_eq!(events[0].user_data, 7);
+    }
+}
@@ -0,0 +1,363 @@
+use bevy::{
+    math::{Rect, Size},
+    prelude::*,
+    utils::Uuid,
+};
+
+use crate::{
+    channels::{StyleChannels, TextChannels},
+    implemented::add_color,
+    lerp::Lerp,
+    Keyframe, KeyframeClipUuid,
+};
+
+/// Interpolate between two values of the same unit. Values of different
+/// units, `Auto` or `Undefined` can't be interpolated and switch to `to`.
+fn lerp_val(from: Val, to: Val, scalar: f32) -> Val {
+    match (from, to) {
+        (Val::Px(from), Val::Px(to)) => Val::Px(from + (to - from) * scalar),
+        (Val::Percent(from), Val::Percent(to)) => Val::Percent(from + (to - from) * scalar),
+        (_, to) => to,
+    }
+}
+
+/// Add `delta` to `base` if they have the same unit. An `Undefined` delta
+/// keeps `base`, and one of another unit overrides it.
+fn add_val(base: Val, delta: Val) -> Val {
+    match (base, delta) {
+        (Val::Px(base), Val::Px(delta)) => Val::Px(base + delta),
+        (Val::Percent(base), Val::Percent(delta)) => Val::Percent(base + delta),
+        (base, Val::Undefined) => base,
+        (_, delta) => delta,
+    }
+}
+
+fn map_rect(a: Rect<Val>, b: Rect<Val>, f: impl Fn(Val, Val) -> Val) -> Rect<Val> {
+    Rect {
+        left: f(a.left, b.left),
+        right: f(a.right, b.right),
+        top: f(a.top, b.top),
+        bottom: f(a.bottom, b.bottom),
+    }
+}
+
+fn map_size(a: Size<Val>, b: Size<Val>, f: impl Fn(Val, Val) -> Val) -> Size<Val> {
+    Size {
+        width: f(a.width, b.width),
+        height: f(a.height, b.height),
+    }
+}
+
+/// Combine the `channels` of `a` and `b` with `f`, the other attributes are
+/// taken from `target`.
+fn map_style(
+    a: &Style,
+    b: &Style,
+    target: &Style,
+    channels: &StyleChannels,
+    f: impl Fn(Val, Val) -> Val + Copy,
+) -> Style {
+    let mut style = target.clone();
+    if channels.contains(StyleChannels::POSITION) {
+        style.position = map_rect(a.position, b.position, f);
+    }
+    if channels.contains(StyleChannels::SIZE) {
+        style.size = map_size(a.size, b.size, f);
+    }
+    if channels.contains(StyleChannels::MIN_SIZE) {
+        style.min_size = map_size(a.min_size, b.min_size, f);
+    }
+    if channels.contains(StyleChannels::MAX_SIZE) {
+        style.max_size = map_size(a.max_size, b.max_size, f);
+    }
+    if channels.contains(StyleChannels::MARGIN) {
+        style.margin = map_rect(a.margin, b.margin, f);
+    }
+    if channels.contains(StyleChannels::PADDING) {
+        style.padding = map_rect(a.padding, b.padding, f);
+    }
+    if channels.contains(StyleChannels::BORDER) {
+        style.border = map_rect(a.border, b.border, f);
+    }
+    style
+}
+
+impl Lerp<Style> for Keyframe<Style> {
+    type Channels = StyleChannels;
+
+    fn lerp(&self, other: &Self, scalar: f32, target: &Style, channels: &StyleChannels) -> Self {
+        Keyframe(map_style(
+            &self.0,
+            &other.0,
+            target,
+            channels,
+            |from, to| lerp_val(from, to, scalar),
+        ))
+    }
+
+    fn add_to(&self, base: &Style, channels: &StyleChannels) -> Self {
+        Keyframe(map_style(base, &self.0, base, channels, add_val))
+    }
+}
+
+impl Keyframe<Style> {
+    pub fn position(values: Vec<Rect<Val>>) -> Vec<Keyframe<Style>> {
+        values
+            .iter()
+            .map(|v| {
+                Keyframe(Style {
+                    position: *v,
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    pub fn size(values: Vec<Size<Val>>) -> Vec<Keyframe<Style>> {
+        values
+            .iter()
+            .map(|v| {
+                Keyframe(Style {
+                    size: *v,
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+}
+
+impl Lerp<UiColor> for Keyframe<UiColor> {
+    type Channels = ();
+
+    fn lerp(&self, other: &Self, scalar: f32, target: &UiColor, _: &()) -> Self {
+        let color = Keyframe(self.0 .0).lerp(&Keyframe(other.0 .0), scalar, &target.0, &());
+        Keyframe(UiColor(color.0))
+    }
+
+    /// Like [`Color`], the alpha of `base` is kept
+    fn add_to(&self, base: &UiColor, _: &()) -> Self {
+        Keyframe(UiColor(add_color(base.0, self.0 .0)))
+    }
+}
+
+impl Keyframe<UiColor> {
+    pub fn colors(values: Vec<Color>) -> Vec<Keyframe<UiColor>> {
+        values.into_iter().map(|v| Keyframe(UiColor(v))).collect()
+    }
+}
+
+impl Lerp<Text> for Keyframe<Text> {
+    type Channels = TextChannels;
+
+    fn lerp(&self, other: &Self, scalar: f32, target: &Text, channels: &TextChannels) -> Self {
+        let mut text = target.clone();
+        // Sections are matched by index, the ones missing from a keyframe
+        // keep their value
+        let sections = text
+            .sections
+            .iter_mut()
+            .zip(&self.0.sections)
+            .zip(&other.0.sections);
+        for ((section, from), to) in sections {
+            if channels.contains(TextChannels::COLOR) {
+                let color = Keyframe(from.style.color).lerp(
+                    &Keyframe(to.style.color),
+                    scalar,
+                    &section.style.color,
+                    &(),
+                );
+                section.style.color = color.0;
+            }
+            if channels.contains(TextChannels::FONT_SIZE) {
+                section.style.font_size =
+                    from.style.font_size + (to.style.font_size - from.style.font_size) * scalar;
+            }
+        }
+        Keyframe(text)
+    }
+
+    /// Like [`Color`], the alpha of the sections of `base` is kept
+    fn add_to(&self, base: &Text, channels: &TextChannels) -> Self {
+        let mut text = base.clone();
+        for (section, delta) in text.sections.iter_mut().zip(&self.0.sections) {
+            if channels.contains(TextChannels::COLOR) {
+                section.style.color = add_color(section.style.color, delta.style.color);
+            }
+            if channels.contains(TextChannels::FONT_SIZE) {
+                section.style.font_size += delta.style.font_size;
+            }
+        }
+        Keyframe(text)
+    }
+}
+
+impl Lerp<UiImage> for Keyframe<UiImage> {
+    type Channels = ();
+
+    fn lerp(&self, other: &Self, _: f32, _: &UiImage, _: &()) -> Self {
+        Keyframe(other.0.clone())
+    }
+}
+
+impl KeyframeClipUuid for Style {
+    const CLIP_UUID: Uuid = Uuid::from_u128(0x3e7a9c5d_24f1_4b68_a0d3_6c8e1f5b9a27);
+}
+
+impl KeyframeClipUuid for UiColor {
+    const CLIP_UUID: Uuid = Uuid::from_u128(0x8b2f6e41_d7a3_4c95_b1e8_0f4a7d3c6e92);
+}
+
+impl KeyframeClipUuid for Text {
+    const CLIP_UUID: Uuid = Uuid::from_u128(0x51c8d3a6_9e2b_47f0_8d65_b3a1e7f2c048);
+}
+
+impl KeyframeClipUuid for UiImage {
+    const CLIP_UUID: Uuid = Uuid::from_u128(0xe6d4b2f8_7a19_4e3c_92b5_1c8f6a0d7e34);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::{AssetPlugin, HandleId},
+        core::CorePlugin,
+    };
+
+    use super::*;
+    use crate::{
+        tween::Tween, KeyframeAnimationClip, KeyframeAnimationPlayer, KeyframeAnimationPlugin,
+    };
+
+    #[test]
+    fn menus_slide_and_fade_through_the_plugin() {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_plugin(KeyframeAnimationPlugin);
+
+        let left = |left| Style {
+            position: Rect {
+                left,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let slide = KeyframeAnimationClip::builder()
+            .channel(StyleChannels::POSITION)
+            .key(0.0, left(Val::Percent(-100.0)))
+            .key(1.0, left(Val::Percent(0.0)))
+            .build()
+            .unwrap();
+        let fade = KeyframeAnimationClip::builder()
+            .key(0.0, UiColor(Color::rgba(1.0, 1.0, 1.0, 0.0)))
+            .key(1.0, UiColor(Color::rgba(1.0, 1.0, 1.0, 1.0)))
+            .build()
+            .unwrap();
+        let slide = app
+            .world
+            .resource_mut::<Assets<KeyframeAnimationClip<Style>>>()
+            .add(slide);
+        let fade = app
+            .world
+            .resource_mut::<Assets<KeyframeAnimationClip<UiColor>>>()
+            .add(fade);
+
+        let mut slide = KeyframeAnimationPlayer::new(slide);
+        slide.set_elapsed(0.5);
+        let mut fade = KeyframeAnimationPlayer::new(fade);
+        fade.set_elapsed(0.5);
+        let image = Handle::weak(HandleId::random::<Image>());
+        let menu = app
+            .world
+            .spawn()
+            .insert_bundle((
+                Style::default(),
+                UiColor::default(),
+                UiImage(Handle::default()),
+                slide,
+                fade,
+                Tween::to(UiImage(image.clone())).duration(0.0),
+            ))
+            .id();
+
+        // The first update has no delta time
+        app.update();
+        let menu = app.world.entity(menu);
+        assert_eq!(
+            menu.get::<Style>().unwrap().position.left,
+            Val::Percent(-50.0)
+        );
+        assert_eq!(menu.get::<UiColor>().unwrap().0.a(), 0.5);
+        assert_eq!(menu.get::<UiImage>().unwrap().0, image);
+        assert!(menu.get::<Tween<UiImage>>().is_none());
+    }
+
+    #[test]
+    fn vals_interpolate_in_the_same_unit() {
+        assert_eq!(lerp_val(Val::Px(10.0), Val::Px(20.0), 0.5), Val::Px(15.0));
+        assert_eq!(
+            lerp_val(Val::Percent(0.0), Val::Percent(50.0), 0.5),
+            Val::Percent(25.0)
+        );
+        assert_eq!(
+            lerp_val(Val::Px(10.0), Val::Percent(50.0), 0.5),
+            Val::Percent(50.0)
+        );
+        assert_eq!(lerp_val(Val::Px(10.0), Val::Auto, 0.5), Val::Auto);
+
+        // Only the animated sides are offset
+        let base = Style {
+            position: Rect {
+                left: Val::Px(100.0),
+                top: Val::Percent(10.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let offset = Keyframe::<Style>::position(vec![Rect {
+            left: Val::Px(-50.0),
+            ..Default::default()
+        }]);
+        let style = offset[0].add_to(&base, &StyleChannels::POSITION).0;
+        assert_eq!(style.position.left, Val::Px(50.0));
+        assert_eq!(style.position.top, Val::Percent(10.0));
+    }
+
+    #[test]
+    fn text_sections_are_interpolated_by_index() {
+        let new_text = |font_size, color| {
+            Text::with_section(
+                "",
+                TextStyle {
+                    font_size,
+                    color,
+                    ..Default::default()
+                },
+                Default::default(),
+            )
+        };
+        let from = Keyframe(new_text(10.0, Color::rgba(1.0, 1.0, 1.0, 0.0)));
+        let to = Keyframe(new_text(20.0, Color::rgba(1.0, 1.0, 1.0, 1.0)));
+        let mut target = new_text(0.0, Color::BLACK);
+        target.sections[0].value = "Play".to_string();
+        target.sections.push(target.sections[0].clone());
+
+        let text = from.lerp(&to, 0.5, &target, &TextChannels::default()).0;
+        assert_eq!(text.sections[0].value, "Play");
+        assert_eq!(text.sections[0].style.font_size, 15.0);
+        assert_eq!(text.sections[0].style.color.a(), 0.5);
+        // Missing from the keyframes
+        assert_eq!(text.sections[1].style.font_size, 0.0);
+
+        // Offsets keep the alpha of the base
+        let faded = Keyframe(new_text(0.0, Color::rgba(1.0, 1.0, 1.0, 0.5)));
+        let tinted = Keyframe(new_text(2.0, Color::rgb(0.0, 0.0, 0.25)))
+            .add_to(&faded.0, &TextChannels::default())
+            .0;
+        assert_eq!(tinted.sections[0].style.font_size, 2.0);
+        assert_eq!(tinted.sections[0].style.color.a(), 0.5);
+        let color = Keyframe(UiColor(Color::rgb(0.25, 0.0, 0.0)))
+            .add_to(&UiColor(Color::rgba(0.0, 0.0, 0.0, 0.5)), &())
+            .0;
+        assert_eq!(color.0, Color::rgba(0.25, 0.0, 0.0, 0.5));
+    }
+}